@@ -0,0 +1,75 @@
+//! Compares `is_valid`, which reads the generation stored in the slot,
+//! with the former check inferring the generation from the position of
+//! the slot relative to the next write
+//!
+//! Run with `cargo bench`.
+
+use {
+    generational_buffer::{
+        GenerationalBuffer,
+        Handle,
+    },
+    std::{
+        hint::black_box,
+        time::Instant,
+    },
+};
+
+const CAPACITY: usize = 1_000;
+const HANDLES: usize = 10_000;
+const ROUNDS: usize = 1_000;
+
+/// The state needed by the former inferred check
+struct Inferred {
+    len: usize,
+    next_index: usize,
+    current_generation: u32,
+}
+
+impl Inferred {
+    fn is_valid<T>(&self, handle: &Handle<T>) -> bool {
+        if handle.index() >= self.len {
+            return false;
+        }
+        let generation = if handle.index() < self.next_index {
+            self.current_generation
+        } else {
+            self.current_generation.saturating_sub(1)
+        };
+        handle.generation() == generation
+    }
+}
+
+fn measure(name: &str, mut f: impl FnMut() -> usize) {
+    let start = Instant::now();
+    let mut valid = 0;
+    for _ in 0..ROUNDS {
+        valid += f();
+    }
+    let elapsed = start.elapsed();
+    let per_check = elapsed.as_nanos() as f64 / (ROUNDS * HANDLES) as f64;
+    println!("{name:>10}: {per_check:.2} ns per check ({valid} valid)");
+}
+
+fn main() {
+    let mut buffer = GenerationalBuffer::new(CAPACITY);
+    let handles: Vec<_> = (0..HANDLES).map(|i| buffer.push(i)).collect();
+    let newest = handles.last().unwrap();
+    let inferred = Inferred {
+        len: buffer.len(),
+        next_index: (newest.index() + 1) % CAPACITY,
+        current_generation: newest.generation() + u32::from(newest.index() + 1 == CAPACITY),
+    };
+    measure("inferred", || {
+        black_box(&handles)
+            .iter()
+            .filter(|h| inferred.is_valid(h))
+            .count()
+    });
+    measure("per-slot", || {
+        black_box(&handles)
+            .iter()
+            .filter(|&&h| buffer.is_valid(h))
+            .count()
+    });
+}
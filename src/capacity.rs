@@ -0,0 +1,13 @@
+/// The maximal number of entries of a buffer
+///
+/// Wrapping the number makes call sites more explicit, eg
+/// `GenerationalBuffer::new(Capacity(16))`, but a bare `usize` is
+/// accepted too wherever a `Capacity` is expected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Capacity(pub usize);
+
+impl From<usize> for Capacity {
+    fn from(capacity: usize) -> Self {
+        Self(capacity)
+    }
+}
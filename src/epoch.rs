@@ -0,0 +1,7 @@
+/// A point in the history of a buffer, to compare with later points
+///
+/// It's the [logical index](crate::GenerationalBuffer::logical_index) the
+/// next pushed entry gets, so it's only meaningful for the buffer it comes
+/// from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Epoch(pub u64);
@@ -0,0 +1,25 @@
+use std::fmt;
+
+/// The reason why two mutable references couldn't be obtained with
+/// [`GenerationalBuffer::get_mut_two`](crate::GenerationalBuffer::get_mut_two)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TwoMutError {
+    /// Both handles point to the same entry
+    SameSlot,
+    /// The first handle isn't valid
+    FirstInvalid,
+    /// The second handle isn't valid
+    SecondInvalid,
+}
+
+impl fmt::Display for TwoMutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SameSlot => write!(f, "both handles point to the same entry"),
+            Self::FirstInvalid => write!(f, "the first handle isn't valid"),
+            Self::SecondInvalid => write!(f, "the second handle isn't valid"),
+        }
+    }
+}
+
+impl std::error::Error for TwoMutError {}
@@ -0,0 +1,73 @@
+use std::{
+    fmt,
+    hash::Hash,
+};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The unsigned integer type used for generations, which may be
+/// `u16`, `u32` (the default), or `u64`.
+///
+/// A smaller width wraps sooner: after `2^16` laps of the ring with
+/// `u16`, a stale handle may again be considered valid. Only the slots
+/// can be made smaller, and because of padding only by not using `u64`:
+/// on 64-bit, slots take 24 bytes with `u16` or `u32` and 32 with `u64`,
+/// while handles, which hold a `usize` index, take 16 bytes whatever
+/// the width.
+pub trait Generation: sealed::Sealed + Copy + Eq + Ord + Hash + fmt::Debug {
+    /// The first generation
+    const ZERO: Self;
+
+    /// The biggest generation, after which it wraps to `ZERO`
+    const MAX: Self;
+
+    /// Returns the next generation, wrapping to `ZERO` after `MAX`
+    fn wrapping_next(self) -> Self;
+
+    /// Returns the signed number of generations from `self` to `other`,
+    /// computed with wrapping arithmetic
+    fn wrapping_distance(self, other: Self) -> i64;
+
+    /// Converts the generation to a `u64`, without loss
+    fn to_u64(self) -> u64;
+}
+
+/// What a buffer does when its generation would overflow, that is when
+/// the ring wraps (or the buffer is cleared) with a generation of `MAX`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GenerationOverflow {
+    /// Wrap around to `ZERO`, so that a handle kept for a full cycle of
+    /// generations may again be considered valid (the default)
+    #[default]
+    Wrap,
+    /// Stop incrementing the generation. Pushing still works, but a stale
+    /// handle of a slot written at `MAX` resolves to the newer value.
+    Saturate,
+    /// Panic, so that no handle can ever be ambiguous
+    Panic,
+}
+
+macro_rules! impl_generation {
+    ($unsigned:ty, $signed:ty) => {
+        impl sealed::Sealed for $unsigned {}
+        impl Generation for $unsigned {
+            const ZERO: Self = 0;
+            const MAX: Self = <$unsigned>::MAX;
+            fn wrapping_next(self) -> Self {
+                self.wrapping_add(1)
+            }
+            fn wrapping_distance(self, other: Self) -> i64 {
+                other.wrapping_sub(self) as $signed as i64
+            }
+            fn to_u64(self) -> u64 {
+                self as u64
+            }
+        }
+    };
+}
+
+impl_generation!(u16, i16);
+impl_generation!(u32, i32);
+impl_generation!(u64, i64);
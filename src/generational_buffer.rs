@@ -1,317 +1,3790 @@
-use std::{
-    fmt,
-    marker::PhantomData,
+use {
+    crate::{
+        Capacity,
+        Epoch,
+        Generation,
+        GenerationOverflow,
+        Handle,
+        Handles,
+        Iter,
+        TwoMutError,
+        Values,
+        slot::Slot,
+    },
+    std::{
+        borrow::Borrow,
+        collections::{
+            HashMap,
+            HashSet,
+            TryReserveError,
+            VecDeque,
+        },
+        fmt,
+        hash::Hash,
+        iter::{
+            FusedIterator,
+            Rev,
+        },
+        ops::Range,
+    },
 };
 
-/// A handle that combines an index with a generation counter.
-///
-/// The handle is typed according to the type of data it refers to,
-/// but doesn't hold it.
-#[derive(Debug)]
-pub struct Handle<T> {
-    index: usize,
-    generation: u32,
-    phantom: PhantomData<T>,
-}
-
-impl<T> Handle<T> {
-    fn new(index: usize, generation: u32) -> Self {
-        Self {
-            index,
-            generation,
-            phantom: PhantomData,
-        }
-    }
-}
-
-/// A generic append-only circular buffer with generational IDs
+/// A generic circular buffer with generational IDs
 ///
 /// Inserting returns a `Handle` that can be used to access the value later,
 /// checking the item hasn't been replaced in the meantime.
 ///
-/// Items can't be individually removed, but the entire buffer can be cleared,
-///  which invalidates all existing handles.
-pub struct GenerationalBuffer<T> {
-    entries: Vec<T>,
+/// The values are stored in chronological order, apart from the slots the
+/// handles point to, which hold the generations. Entries can be removed (eg
+/// with `extract_if`) without affecting the other handles, and the entire
+/// buffer can be cleared, which invalidates all existing handles.
+pub struct GenerationalBuffer<T, G: Generation = u32> {
+    values: VecDeque<T>,
+    // the index of the slot of each value
+    owners: VecDeque<usize>,
+    slots: Vec<Slot<G>>,
+    // the vacant slots, in the order they were freed
+    free: VecDeque<usize>,
+    // what the positions of the slots are counted from
+    front: usize,
     max_capacity: usize,
-    next_index: usize,
-    current_generation: u32,
+    capacity_ceiling: usize,
+    len: usize,
+    current_generation: G,
+    generation_overflow: GenerationOverflow,
+    total_pushed: u64,
+    total_evicted: u64,
+    on_evict: Option<EvictionCallback<T>>,
 }
 
+/// A function receiving the values evicted from a buffer
+///
+/// It doesn't have to be `Sync`, so buffers, which may hold one, aren't.
+pub type EvictionCallback<T> = Box<dyn FnMut(T) + Send>;
+
+/// An evicted entry, with the handle it had
+pub(crate) type Evicted<T, G> = (Handle<T, G>, T);
+
+/// Handles split into the valid ones and the stale ones
+pub(crate) type Partition<T, G> = (Vec<Handle<T, G>>, Vec<Handle<T, G>>);
+
 impl<T> GenerationalBuffer<T> {
     /// Creates a new generational buffer with the specified capacity
     ///
     /// Minimum capacity is 1.
-    pub fn new(max_capacity: usize) -> Self {
-        let max_capacity = max_capacity.max(1);
+    pub fn new<C: Into<Capacity>>(max_capacity: C) -> Self {
+        Self::with_generation_width(max_capacity)
+    }
+}
+
+impl<T, G: Generation> GenerationalBuffer<T, G> {
+    /// Creates a new generational buffer with the specified capacity,
+    /// using `G` for generations, eg
+    /// `GenerationalBuffer::<T, u16>::with_generation_width(16)`
+    ///
+    /// Minimum capacity is 1.
+    pub fn with_generation_width<C: Into<Capacity>>(max_capacity: C) -> Self {
+        let max_capacity = max_capacity.into().0.max(1);
 
         Self {
-            entries: Vec::new(),
+            values: VecDeque::new(),
+            owners: VecDeque::new(),
+            slots: Vec::new(),
+            free: VecDeque::new(),
+            front: 0,
             max_capacity,
-            next_index: 0,
-            current_generation: 0,
+            capacity_ceiling: max_capacity,
+            len: 0,
+            current_generation: G::ZERO,
+            generation_overflow: GenerationOverflow::Wrap,
+            total_pushed: 0,
+            total_evicted: 0,
+            on_evict: None,
+        }
+    }
+
+    /// Sets a function to call with the values evicted by `push`, instead
+    /// of just dropping them
+    ///
+    /// The callback is called when the entry has been removed, before the
+    /// new value is written: if it panics, the new value isn't stored. The
+    /// callback can't access the buffer: pushing from within the callback
+    /// (re-entrancy) isn't supported.
+    pub fn on_evict(mut self, callback: EvictionCallback<T>) -> Self {
+        self.on_evict = Some(callback);
+        self
+    }
+
+    /// Makes the buffer grow instead of evicting entries, by doubling its
+    /// capacity each time it's reached, until the ceiling
+    ///
+    /// Once the capacity is the ceiling, pushing evicts the oldest entry.
+    /// Growing keeps all handles valid. A ceiling smaller than the current
+    /// capacity is ignored.
+    pub fn grow_until(mut self, ceiling: usize) -> Self {
+        self.capacity_ceiling = ceiling.max(self.max_capacity);
+        self
+    }
+
+    /// Sets what happens when the generation would overflow, instead of
+    /// wrapping around
+    ///
+    /// Wrapping needs `2^32` laps of the ring with the default `u32`
+    /// generations, but may be a concern with `u16` ones.
+    pub fn on_generation_overflow(mut self, mode: GenerationOverflow) -> Self {
+        self.generation_overflow = mode;
+        self
+    }
+
+    /// Returns true if the generation reached `MAX` and doesn't increment
+    /// anymore, which happens only with [`GenerationOverflow::Saturate`]
+    ///
+    /// A saturated buffer can't detect all stale handles.
+    pub fn is_generation_saturated(&self) -> bool {
+        self.generation_overflow == GenerationOverflow::Saturate
+            && self.current_generation == G::MAX
+    }
+
+    /// Increments the generation, according to the overflow mode
+    fn advance_generation(&mut self) {
+        if self.current_generation == G::MAX {
+            match self.generation_overflow {
+                GenerationOverflow::Wrap => {}
+                GenerationOverflow::Saturate => return,
+                GenerationOverflow::Panic => panic!("generation overflow"),
+            }
         }
+        self.current_generation = self.current_generation.wrapping_next();
+    }
+
+    /// Returns the maximum capacity of the buffer, that is the number of
+    /// entries it can hold before evicting the oldest ones
+    ///
+    /// Contrary to `Vec::capacity`, this isn't about allocated memory,
+    /// see [`allocated_capacity`](Self::allocated_capacity) for that.
+    pub const fn capacity(&self) -> usize {
+        self.max_capacity
+    }
+
+    /// Returns the capacity of a buffer whose entries fit in the given
+    /// number of bytes, with a minimum of 1
+    ///
+    /// An entry holds the value, its slot and the index of its slot so
+    /// it's never zero-sized, even when `T` is. Values owning heap memory
+    /// (eg `String`) aren't taken into account beyond their inline size.
+    pub const fn capacity_for_bytes(bytes: usize) -> usize {
+        let entry = std::mem::size_of::<T>()
+            + std::mem::size_of::<usize>()
+            + std::mem::size_of::<Slot<G>>();
+        let capacity = bytes / entry;
+        if capacity == 0 { 1 } else { capacity }
     }
 
-    /// Returns the maximum capacity of the buffer
-    pub fn capacity(&self) -> usize {
+    /// Returns the maximum capacity of the buffer, same as
+    /// [`capacity`](Self::capacity)
+    pub const fn max_capacity(&self) -> usize {
         self.max_capacity
     }
 
+    /// Returns the number of values the buffer has allocated memory for
+    ///
+    /// Memory is allocated as the buffer grows, so this may be smaller than
+    /// [`capacity`](Self::capacity) before the buffer is first filled, or
+    /// bigger as the allocator may give more than needed.
+    pub fn allocated_capacity(&self) -> usize {
+        self.values.capacity()
+    }
+
+    /// Allocates the memory for all the entries, without the slack
+    /// collections may add when growing
+    ///
+    /// Contrary to [`reserve`](Self::reserve), this doesn't push anything.
+    pub fn reserve_exact(&mut self) {
+        let additional = self.max_capacity - self.len;
+        self.values.reserve_exact(additional);
+        self.owners.reserve_exact(additional);
+        self.slots
+            .reserve_exact(self.max_capacity.saturating_sub(self.slots.len()));
+    }
+
+    /// Allocates the memory for all the entries, returning an error instead
+    /// of aborting if the allocation fails
+    pub fn try_reserve(&mut self) -> Result<(), TryReserveError> {
+        let additional = self.max_capacity - self.len;
+        self.values.try_reserve(additional)?;
+        self.owners.try_reserve(additional)?;
+        self.slots
+            .try_reserve(self.max_capacity.saturating_sub(self.slots.len()))
+    }
+
     /// Returns the current number of entries in the buffer
-    pub fn len(&self) -> usize {
-        self.entries.len()
+    pub const fn len(&self) -> usize {
+        self.len
     }
 
     /// Returns true if the buffer is empty
-    pub fn is_empty(&self) -> bool {
-        self.entries.is_empty()
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
     }
 
     /// Clear the buffer, removing all entries and rendering all
     /// existing handles invalid.
+    ///
+    /// The generation is always advanced, so that a value pushed after
+    /// the clear never gets the handle of a value pushed before.
     pub fn clear(&mut self) {
-        self.entries.clear();
-        self.next_index = 0;
-        self.current_generation += 2;
+        self.values.clear();
+        self.owners.clear();
+        self.slots.clear();
+        self.free.clear();
+        self.front = 0;
+        self.len = 0;
+        self.advance_generation();
     }
 
-    /// Returns true if the buffer has reached its maximum capacity
-    pub fn is_full(&self) -> bool {
-        self.entries.len() == self.max_capacity
+    /// Replaces all entries with the given values, keeping only the newest
+    /// ones when there are more than the capacity
+    ///
+    /// As with `clear`, all existing handles are invalidated, but the
+    /// generation is advanced only once for the whole batch, so that all
+    /// new entries share the same generation. Neither the removed entries
+    /// nor the values which don't fit are given to the eviction callback,
+    /// but the latter are counted as pushed and evicted, as with `push`.
+    ///
+    /// The values which don't fit are dropped while iterating, so that at
+    /// most a capacity of values are held at once.
+    pub fn replace_contents<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        // only the last values are kept while iterating
+        let items = items.into_iter();
+        let mut window = VecDeque::with_capacity(items.size_hint().0.min(self.max_capacity));
+        let mut excess = 0;
+        for value in items {
+            if window.len() == self.max_capacity {
+                window.pop_front();
+                excess += 1;
+            }
+            window.push_back(value);
+        }
+        self.clear();
+        self.total_pushed += excess;
+        self.total_evicted += excess;
+        for value in window {
+            // the slots are all new, so they share the current generation
+            self.push_back(value);
+        }
     }
 
-    /// Inserts a value into the buffer and returns a handle to it.
+    /// Changes the capacity, filling the new slots with values produced
+    /// by the function when growing
     ///
-    /// This removes the oldest entry if the buffer is full.
-    pub fn push(&mut self, value: T) -> Handle<T> {
-        let index = self.next_index;
-        let generation = self.current_generation;
-
-        if self.entries.len() < self.max_capacity {
-            // Buffer is not full yet, just append
-            self.entries.push(value);
-        } else {
-            // Buffer is full, overwrite the oldest entry
-            self.entries[index] = value;
+    /// Growing makes the storage of the entries contiguous, then the filler
+    /// values are pushed, as the newest entries. All handles stay valid.
+    ///
+    /// When shrinking, this is [`shrink_capacity_keeping_newest`](Self::shrink_capacity_keeping_newest):
+    /// only the newest entries are kept.
+    pub fn resize_with<F: FnMut() -> T>(&mut self, new_capacity: usize, mut f: F) {
+        let new_capacity = new_capacity.max(1);
+        let old_capacity = self.max_capacity;
+        if new_capacity > old_capacity {
+            self.max_capacity = new_capacity;
+            self.capacity_ceiling = self.capacity_ceiling.max(new_capacity);
+            // the fillers must not wrap around the storage
+            let mut values = Vec::with_capacity(new_capacity);
+            values.extend(self.values.drain(..));
+            self.values = values.into();
+            for _ in old_capacity..new_capacity {
+                self.push(f());
+            }
+        } else if new_capacity < old_capacity {
+            self.shrink_capacity_keeping_newest(new_capacity);
         }
+    }
 
-        // Create handle with current generation
-        let handle = Handle::new(index, generation);
-
-        // Advance to the next position
-        self.next_index = (self.next_index + 1) % self.max_capacity;
+    /// Reduces the capacity, keeping only the newest entries, the other
+    /// ones being given to the eviction callback
+    ///
+    /// The handles of the kept entries stay valid. Nothing is done if the
+    /// capacity isn't reduced. See [`try_shrink`](Self::try_shrink) to
+    /// prevent any loss.
+    ///
+    /// The new capacity is also the new ceiling: a buffer which could
+    /// [grow](Self::grow_until) doesn't grow anymore.
+    pub fn shrink_capacity_keeping_newest(&mut self, new_capacity: usize) {
+        let new_capacity = new_capacity.max(1);
+        if new_capacity >= self.max_capacity {
+            return;
+        }
+        while self.len > new_capacity {
+            let (_, value) = self.remove_at(0);
+            self.total_evicted += 1;
+            self.evict(value);
+        }
+        self.max_capacity = new_capacity;
+        self.capacity_ceiling = new_capacity;
+    }
 
-        // If we've wrapped around, increment the generation
-        if self.next_index == 0 {
-            self.current_generation = self.current_generation.wrapping_add(1);
+    /// Reduces the capacity as [`shrink_capacity_keeping_newest`](Self::shrink_capacity_keeping_newest)
+    /// does, but only if no entry would be lost
+    ///
+    /// Returns the number of entries which would be lost otherwise, and
+    /// leaves the buffer unchanged in that case.
+    pub fn try_shrink(&mut self, new_capacity: usize) -> Result<(), usize> {
+        let lost = self.len.saturating_sub(new_capacity.max(1));
+        if lost > 0 {
+            return Err(lost);
         }
+        self.shrink_capacity_keeping_newest(new_capacity);
+        Ok(())
+    }
 
-        handle
+    /// Reduces the capacity to the number of entries (or 1 if empty), so
+    /// that the next push evicts the oldest entry
+    ///
+    /// All entries and their handles are kept. The buffer doesn't grow
+    /// anymore.
+    pub fn set_capacity_to_len(&mut self) {
+        self.max_capacity = self.len.max(1);
+        self.capacity_ceiling = self.max_capacity;
     }
 
-    /// Gets a reference to the value associated with the handle
-    pub fn get(&self, handle: Handle<T>) -> Option<&T> {
-        if self.is_valid(handle) {
-            Some(&self.entries[handle.index])
-        } else  {
-            None
+    /// Rewrite the generations of all slots to the smallest consistent
+    /// set: 1 for the slots written at the current generation, 0 for the
+    /// older ones.
+    ///
+    /// Returns the `(old, new)` handle pairs of the live entries, in
+    /// chronological order, so that stored handles can be updated.
+    /// Handles not in this remapping must be discarded as they could
+    /// resolve to a different entry after the recalibration.
+    pub fn recalibrate_generations(&mut self) -> Vec<(Handle<T, G>, Handle<T, G>)> {
+        let previous = G::ZERO;
+        let current = previous.wrapping_next();
+        let recalibrated = |generation| {
+            if generation == self.current_generation { current } else { previous }
+        };
+        let remapping = self
+            .owners
+            .iter()
+            .map(|&index| {
+                let generation = self.slots[index].generation;
+                (
+                    Handle::new(index, generation),
+                    Handle::new(index, recalibrated(generation)),
+                )
+            })
+            .collect();
+        for slot in &mut self.slots {
+            slot.generation = recalibrated(slot.generation);
         }
+        self.current_generation = current;
+        remapping
     }
 
-    /// Gets a mutable reference to the value associated with the handle
-    pub fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
-        if self.is_valid(handle) {
-            Some(&mut self.entries[handle.index])
-        } else  {
-            None
-        }
+    /// Returns the number of values pushed since the buffer was created,
+    /// whatever the push method
+    pub const fn total_pushed(&self) -> u64 {
+        self.total_pushed
     }
 
-    /// Checks if a handle is still valid (points to existing data)
-    pub fn is_valid(&self, handle: Handle<T>) -> bool {
-        if handle.index >= self.entries.len() {
-            return false;
-        }
-        handle.generation == self.calculate_generation_at_index(handle.index)
+    /// Returns the number of entries evicted by pushes since the buffer
+    /// was created
+    ///
+    /// Entries removed (eg by `take`) or cleared aren't counted, so without
+    /// removal this is `total_pushed() - len()`.
+    pub const fn total_evicted(&self) -> u64 {
+        self.total_evicted
     }
 
-    /// Returns an iterator over all entries with their handles,
-    ///  in no particular order
-    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &T)> {
-        self.entries.iter().enumerate().map(|(i, value)| {
-            let generation = self.calculate_generation_at_index(i);
-            (Handle::new(i, generation), value)
-        })
+    /// Returns true if the buffer has reached its maximum capacity
+    pub const fn is_full(&self) -> bool {
+        self.len == self.max_capacity
+    }
+
+    /// Inserts a value into the buffer and returns a handle to it.
+    ///
+    /// This removes the oldest entry if the buffer is full.
+    ///
+    /// The evicted value is given to the [`on_evict`](Self::on_evict)
+    /// callback, if any.
+    pub fn push(&mut self, value: T) -> Handle<T, G> {
+        if let Some((_, evicted)) = self.make_room() {
+            self.evict(evicted);
+        }
+        self.push_back(value)
     }
 
-    /// Returns an iterator over all entries, in no particular order
-    pub fn values(&self) -> impl Iterator<Item = &T> {
-        self.entries.iter()
+    /// Inserts a value into the buffer and returns a handle to it, together
+    /// with a mutable reference to the stored value
+    ///
+    /// This saves a `get_mut` lookup when the new entry must be modified.
+    pub fn push_mut(&mut self, value: T) -> (Handle<T, G>, &mut T) {
+        let handle = self.push(value);
+        let value = self
+            .values
+            .back_mut()
+            .expect("the pushed value should be the newest one");
+        (handle, value)
     }
 
-    /// Returns an iterator over all valid handles, in no particular order
-    pub fn handles(&self) -> impl Iterator<Item = Handle<T>> + '_ {
-        (0..self.entries.len()).map(move |i| {
-            let generation = self.calculate_generation_at_index(i);
-            Handle::new(i, generation)
-        })
+    /// Inserts a value before the oldest entry, and returns a handle to it
+    ///
+    /// If the buffer is full, the newest entry is evicted, as `push` evicts
+    /// the oldest one. The new entry being the oldest one, it's the next
+    /// to be evicted by `push`.
+    ///
+    /// Other handles stay valid. The [logical index](Self::logical_index)
+    /// of the new entry is the number of values pushed before it, so it
+    /// doesn't follow the chronological order.
+    pub fn push_front(&mut self, value: T) -> Handle<T, G> {
+        self.grow_if_full();
+        if self.is_full() {
+            let (_, evicted) = self.remove_at(self.len - 1);
+            self.total_evicted += 1;
+            self.evict(evicted);
+        }
+        let index = self.vacant_slot();
+        self.front = self.front.wrapping_sub(1);
+        let generation = self.occupy(index, self.front);
+        self.values.push_front(value);
+        self.owners.push_front(index);
+        self.len += 1;
+        Handle::new(index, generation)
     }
 
-    /// Calculate what generation should be at a given index
-    fn calculate_generation_at_index(&self, index: usize) -> u32 {
-        if index < self.next_index {
-            self.current_generation
+    /// Rotates the entries so that, with `n > 0`, the `n` oldest entries
+    /// become the newest ones or, with `n < 0`, the `-n` newest entries
+    /// become the oldest ones
+    ///
+    /// On a buffer holding `[a, b, c, d]` from the oldest to the newest,
+    /// `rotate(1)` makes it `[b, c, d, a]`, and the next push evicts `b` if
+    /// the buffer is full. `n` is taken modulo the number of entries.
+    ///
+    /// All handles stay valid, and the generation isn't changed. This is
+    /// O(n) in the number of entries.
+    pub fn rotate(&mut self, n: isize) {
+        if self.is_empty() {
+            return;
+        }
+        let len = self.len;
+        let shift = n.unsigned_abs() % len;
+        if n >= 0 {
+            self.values.rotate_left(shift);
+            self.owners.rotate_left(shift);
         } else {
-            self.current_generation.saturating_sub(1)
+            self.values.rotate_right(shift);
+            self.owners.rotate_right(shift);
         }
+        self.renumber();
     }
-}
 
-impl<T: fmt::Debug> fmt::Debug for GenerationalBuffer<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("GenerationalBuffer")
-            .field("capacity", &self.capacity())
-            .field("len", &self.len())
-            .field("next_index", &self.next_index)
-            .field("current_generation", &self.current_generation)
-            .field("entries", &self.entries)
-            .finish()
+    /// Pushes values produced by the function until the buffer is full,
+    /// and returns their handles, from the oldest to the newest
+    ///
+    /// A buffer which can [grow](Self::grow_until) is filled up to its
+    /// current capacity, not to its ceiling.
+    pub fn fill_with<F: FnMut() -> T>(&mut self, mut f: F) -> Vec<Handle<T, G>> {
+        let mut handles = Vec::with_capacity(self.max_capacity - self.len);
+        while !self.is_full() {
+            handles.push(self.push(f()));
+        }
+        handles
     }
-}
-impl<O> PartialEq for Handle<O> {
-    fn eq(&self, other: &Self) -> bool {
-        self.index == other.index && self.generation == other.generation
+
+    /// Inserts a value and returns a handle to it, together with the
+    /// handle of the entry it evicted, if any.
+    ///
+    /// The evicted handle is the one which was returned when the removed
+    /// entry was pushed, and which is now invalid.
+    pub fn push_tracking(&mut self, value: T) -> (Handle<T, G>, Option<Handle<T, G>>) {
+        let evicted = self.make_room().map(|(evicted_handle, evicted)| {
+            self.evict(evicted);
+            evicted_handle
+        });
+        (self.push_back(value), evicted)
     }
-}
-impl<O> Eq for Handle<O> {}
-impl<O> Clone for Handle<O> {
-    fn clone(&self) -> Self {
-        *self
+
+    /// Inserts a value and returns a handle to it, together with the value
+    /// it evicted, if any, so that the caller chooses when to drop it
+    ///
+    /// This keeps possibly expensive destructors out of hot paths.
+    /// The [`on_evict`](Self::on_evict) callback isn't called.
+    pub fn push_deferring_drop(&mut self, value: T) -> (Handle<T, G>, Option<T>) {
+        let (handle, evicted) = self.push_evicting(value);
+        (handle, evicted.map(|(_, value)| value))
     }
-}
-impl<O> Copy for Handle<O> {}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Inserts all the values and returns their handles, in order, together
+    /// with the values evicted during the batch, in eviction order
+    ///
+    /// As with [`push_deferring_drop`](Self::push_deferring_drop), the
+    /// [`on_evict`](Self::on_evict) callback isn't called. Values pushed
+    /// and then evicted in the same batch are also returned.
+    pub fn push_many_tracking<I: IntoIterator<Item = T>>(
+        &mut self,
+        items: I,
+    ) -> (Vec<Handle<T, G>>, Vec<T>) {
+        let items = items.into_iter();
+        let mut handles = Vec::with_capacity(items.size_hint().0);
+        let mut evicted = Vec::new();
+        for value in items {
+            let (handle, evicted_entry) = self.push_evicting(value);
+            handles.push(handle);
+            evicted.extend(evicted_entry.map(|(_, value)| value));
+        }
+        (handles, evicted)
+    }
 
-    #[test]
-    fn test_basic_operations() {
-        let mut buffer = GenerationalBuffer::new(3);
+    /// Inserts all the values, giving the evicted ones to `on_evict` as
+    /// soon as they're evicted
+    ///
+    /// The [`on_evict`](Self::on_evict) callback of the buffer isn't
+    /// called for those values.
+    pub fn extend_with_evictions<I: IntoIterator<Item = T>>(
+        &mut self,
+        items: I,
+        mut on_evict: impl FnMut(T),
+    ) {
+        for value in items {
+            if let (_, Some((_, evicted))) = self.push_evicting(value) {
+                on_evict(evicted);
+            }
+        }
+    }
 
-        // Insert some values
-        let h1 = buffer.push(10);
-        let h2 = buffer.push(20);
-        let h3 = buffer.push(30);
+    /// Gives an evicted value to the eviction callback, if any, or drops it
+    fn evict(&mut self, value: T) {
+        if let Some(on_evict) = &mut self.on_evict {
+            on_evict(value);
+        }
+    }
 
-        // Check values
-        assert_eq!(buffer.get(h1), Some(&10));
-        assert_eq!(buffer.get(h2), Some(&20));
-        assert_eq!(buffer.get(h3), Some(&30));
-        assert_eq!(buffer.len(), 3);
-        assert!(buffer.is_full());
+    /// Inserts a value, returning its handle and the evicted entry, if any
+    pub(crate) fn push_evicting(&mut self, value: T) -> (Handle<T, G>, Option<Evicted<T, G>>) {
+        let evicted = self.make_room();
+        (self.push_back(value), evicted)
     }
 
-    #[test]
-    fn test_circular_wrapping() {
-        let mut buffer = GenerationalBuffer::new(2);
+    /// Grows the buffer if it's full and below its ceiling, or evicts the
+    /// oldest entry if it's full, so that a value can be inserted
+    fn make_room(&mut self) -> Option<Evicted<T, G>> {
+        // new() ensures the capacity is at least 1, this is checked in
+        // release builds too as a zero capacity would break the ring
+        assert!(
+            self.max_capacity > 0,
+            "cannot push into a zero-capacity GenerationalBuffer"
+        );
+        self.grow_if_full();
+        if !self.is_full() {
+            return None;
+        }
+        self.total_evicted += 1;
+        Some(self.remove_at(0))
+    }
 
-        // Fill the buffer
-        let h1 = buffer.push(10);
-        let h2 = buffer.push(20);
+    /// Doubles the capacity, up to the ceiling, if the buffer is full
+    fn grow_if_full(&mut self) {
+        if self.is_full() && self.max_capacity < self.capacity_ceiling {
+            self.max_capacity = self
+                .max_capacity
+                .saturating_mul(2)
+                .min(self.capacity_ceiling);
+        }
+    }
 
-        // Wrap around - this should invalidate h1
-        let h3 = buffer.push(30);
+    /// Inserts a value after the newest entry, the buffer being not full
+    fn push_back(&mut self, value: T) -> Handle<T, G> {
+        let index = self.vacant_slot();
+        let generation = self.occupy(index, self.front.wrapping_add(self.len));
+        self.values.push_back(value);
+        self.owners.push_back(index);
+        self.len += 1;
+        Handle::new(index, generation)
+    }
 
-        // h1 should now be invalid due to generation mismatch
-        assert_eq!(buffer.get(h1), None);
-        assert_eq!(buffer.get(h2), Some(&20));
-        assert_eq!(buffer.get(h3), Some(&30));
-        assert!(!buffer.is_valid(h1));
-        assert!(buffer.is_valid(h2));
-        assert!(buffer.is_valid(h3));
-        assert_eq!(buffer.len(), 2);
+    /// Returns the slot the next inserted value goes to, the buffer being
+    /// not full
+    fn vacant_slot(&mut self) -> usize {
+        if self.slots.len() < self.max_capacity {
+            // new slots are used first, as the ring reaches them
+            return self.slots.len();
+        }
+        self.free
+            .pop_front()
+            .expect("a buffer which isn't full should have a vacant slot")
+    }
 
-        // Do one more turn
-        let h4 = buffer.push(40); // This should overwrite h2
-        let h5 = buffer.push(50); // This should overwrite h3
-        assert_eq!(buffer.get(h4), Some(&40));
-        assert_eq!(buffer.get(h5), Some(&50));
-        assert!(!buffer.is_valid(h2)); // h2 should be invalid now
-        assert!(!buffer.is_valid(h3)); // h3 should be invalid now
+    /// Writes the slot for a value pushed at the given position, and
+    /// returns its generation
+    fn occupy(&mut self, index: usize, position: usize) -> G {
+        if index == self.slots.len() {
+            self.slots.push(Slot {
+                generation: self.current_generation,
+                live: true,
+                position,
+                pushed: self.total_pushed,
+            });
+        } else {
+            if self.slots[index].generation == self.current_generation {
+                // the slot was already written at this generation, which
+                // happens once per lap of the ring when only pushing
+                self.advance_generation();
+            }
+            self.slots[index] = Slot {
+                generation: self.current_generation,
+                live: true,
+                position,
+                pushed: self.total_pushed,
+            };
+        }
+        self.total_pushed += 1;
+        self.current_generation
+    }
 
-        // Clear the buffer
-        buffer.clear();
-        assert!(buffer.is_empty());
-        assert!(!buffer.is_valid(h4)); // h4 should be invalid now
-        let h6 = buffer.push(60); // New handle after clear
-        assert_eq!(buffer.get(h6), Some(&60));
+    /// Inserts a default value into the buffer and returns a handle to it,
+    /// so that the slot can be claimed before the real value is ready.
+    ///
+    /// The real value can then be stored with [`replace`](Self::replace),
+    /// which keeps the handle valid.
+    ///
+    /// As for `push`, this removes the oldest entry if the buffer is full,
+    /// and the reserved entry may itself be evicted by later pushes before
+    /// it's filled.
+    pub fn reserve(&mut self) -> Handle<T, G>
+    where
+        T: Default,
+    {
+        self.push(T::default())
     }
 
-    #[test]
-    fn test_generation_calculation() {
-        let mut buffer = GenerationalBuffer::new(3);
+    /// Replaces the value associated with the handle, returning the old one.
+    ///
+    /// The handle stays valid. Returns `None`, and doesn't store the value,
+    /// if the handle is no longer valid.
+    pub fn replace(&mut self, handle: Handle<T, G>, value: T) -> Option<T> {
+        self.get_mut(handle).map(|entry| std::mem::replace(entry, value))
+    }
 
-        // Fill buffer multiple times
-        let handles: Vec<_> = (0..10).map(|i| buffer.push(i)).collect();
+    /// Replaces the value of the handle if it's still valid, and returns
+    /// this handle, or pushes the value and returns the new handle
+    pub fn upsert(&mut self, handle: Handle<T, G>, value: T) -> Handle<T, G> {
+        match self.get_mut(handle) {
+            Some(entry) => {
+                *entry = value;
+                handle
+            }
+            None => self.push(value),
+        }
+    }
 
-        // Only the last 3 handles should be valid
-        for (i, &handle) in handles.iter().enumerate() {
-            if i < 7 {
-                assert!(!buffer.is_valid(handle), "Handle {i} should be invalid");
-            } else {
-                assert!(buffer.is_valid(handle), "Handle {i} should be valid");
+    /// Lets the newest entry absorb an event, or pushes a new entry
+    ///
+    /// `update` is called on the newest entry, if any, and returns whether
+    /// it absorbed the event. If it didn't, the value built by `make` is
+    /// pushed. The handle of the newest entry is returned in both cases.
+    pub fn coalesce_newest<F: FnMut(&mut T) -> bool>(
+        &mut self,
+        make: impl FnOnce() -> T,
+        mut update: F,
+    ) -> Handle<T, G> {
+        if let Some((handle, newest)) = self.newest_mut() {
+            if update(newest) {
+                return handle;
             }
         }
+        self.push(make())
     }
 
-    #[test]
-    fn test_iterator() {
-        let mut buffer = GenerationalBuffer::new(3);
+    /// Replaces the value of the oldest entry, returning its handle, which
+    /// stays valid, and the old value.
+    ///
+    /// The entry keeps its place: the next push still evicts it, as it's
+    /// still considered the oldest. Returns `None`, and doesn't store the
+    /// value, if the buffer is empty.
+    pub fn replace_oldest(&mut self, value: T) -> Option<(Handle<T, G>, T)> {
+        let (handle, _) = self.iter_chronological().next()?;
+        self.replace(handle, value).map(|old| (handle, old))
+    }
 
-        buffer.push(10);
-        buffer.push(20);
-        buffer.push(30);
-        buffer.push(40);
-        buffer.push(50);
-        buffer.push(60);
-        buffer.push(70);
-        buffer.push(80);
+    /// Replaces the value associated with the handle with the result of
+    /// the function applied to the current value.
+    ///
+    /// Returns false, without calling the function, if the handle is no
+    /// longer valid. If the function panics, the entry is removed.
+    pub fn modify<F: FnOnce(T) -> T>(&mut self, handle: Handle<T, G>, f: F) -> bool {
+        /// Removes the entry whose value was moved out, if `f` panics
+        struct Guard<'b, T, G: Generation> {
+            buffer: &'b mut GenerationalBuffer<T, G>,
+            position: usize,
+        }
+        impl<T, G: Generation> Drop for Guard<'_, T, G> {
+            fn drop(&mut self) {
+                let (_, moved) = self.buffer.remove_at(self.position);
+                // this copy was given to f, which dropped it
+                std::mem::forget(moved);
+            }
+        }
+        let Some(position) = self.position_of(handle) else {
+            return false;
+        };
+        // SAFETY: the value is valid, and it's either overwritten below,
+        // or removed by the guard without being dropped
+        let value = unsafe { std::ptr::read(&self.values[position]) };
+        let guard = Guard {
+            buffer: self,
+            position,
+        };
+        let value = f(value);
+        // SAFETY: the previous value was moved out, it must not be dropped
+        unsafe { std::ptr::write(&mut guard.buffer.values[position], value) };
+        std::mem::forget(guard);
+        true
+    }
 
-        let mut values: Vec<i32> = buffer.values().cloned().collect();
-        values.sort(); // no order is currently guaranteed
-        assert_eq!(values, vec![60, 70, 80]);
+    /// Moves all entries of `other` into this buffer, from the oldest to
+    /// the newest, as if they were pushed, leaving `other` empty.
+    ///
+    /// This evicts the oldest entries of this buffer if needed, as `push` does.
+    /// Handles of `other` are all invalidated and don't carry over: they
+    /// must not be used with this buffer.
+    pub fn merge_newest_from(&mut self, other: &mut GenerationalBuffer<T, G>) {
+        let values = std::mem::take(&mut other.values);
+        other.clear();
+        for value in values {
+            self.push(value);
+        }
+    }
 
-        let handles: Vec<_> = buffer.handles().collect();
-        assert_eq!(handles.len(), 3);
+    /// Removes the entry of the handle, so that this handle becomes invalid
+    /// while the other ones are unaffected
+    ///
+    /// The length decreases, and the slot stays vacant until it's reused.
+    /// Returns false if the handle was already invalid.
+    pub fn invalidate(&mut self, handle: Handle<T, G>) -> bool {
+        self.take(handle).is_some()
+    }
 
-        // Verify all handles are valid
-        for handle in handles {
-            assert!(buffer.is_valid(handle));
-        }
+    /// Removes the entry of the handle and returns its value, so that this
+    /// handle becomes invalid while the other ones are unaffected
+    ///
+    /// As for [`invalidate`](Self::invalidate), the length decreases and
+    /// the slot stays vacant until it's reused. The values between the
+    /// removed one and the nearest end of the buffer are moved, so this is
+    /// O(n) in the worst case.
+    pub fn take(&mut self, handle: Handle<T, G>) -> Option<T> {
+        let position = self.position_of(handle)?;
+        Some(self.remove_at(position).1)
     }
 
-    #[test]
-    fn test_growing_buffer() {
-        let mut buffer = GenerationalBuffer::new(5);
+    /// Removes the entry of the handle and returns its value, keeping the
+    /// chronological order of the other entries
+    ///
+    /// As the values are always stored in chronological order, this is
+    /// the same as [`take`](Self::take): it's O(n) and the handles of the
+    /// other entries stay valid.
+    pub fn remove_stable(&mut self, handle: Handle<T, G>) -> Option<T> {
+        self.take(handle)
+    }
 
-        // Add elements one by one
-        let h1 = buffer.push(1);
-        let h2 = buffer.push(2);
-        let h3 = buffer.push(3);
+    /// Returns the result of the function applied to each entry, from the
+    /// oldest to the newest
+    pub fn project_to_vec<U, F: FnMut(&T) -> U>(&self, mut f: F) -> Vec<U> {
+        self.iter_chronological().map(|(_, value)| f(value)).collect()
+    }
 
-        assert_eq!(buffer.len(), 3);
-        assert!(!buffer.is_full());
+    /// Applies the function to all entries, in place
+    ///
+    /// Nothing is allocated and all handles stay valid.
+    pub fn map_in_place<F: FnMut(&mut T)>(&mut self, f: F) {
+        self.values.iter_mut().for_each(f);
+    }
 
-        // All handles should be valid
-        assert!(buffer.is_valid(h1));
-        assert!(buffer.is_valid(h2));
-        assert!(buffer.is_valid(h3));
+    /// Converts the buffer into a buffer of another type, by applying
+    /// a function to all entries
+    ///
+    /// The slots and their generations are kept, so handles can be
+    /// converted with [`Handle::remap_handle`]. The eviction callback,
+    /// which takes values of the old type, is dropped.
+    pub fn map<U, F: FnMut(T) -> U>(self, f: F) -> GenerationalBuffer<U, G> {
+        GenerationalBuffer {
+            values: self.values.into_iter().map(f).collect(),
+            owners: self.owners,
+            slots: self.slots,
+            free: self.free,
+            front: self.front,
+            max_capacity: self.max_capacity,
+            capacity_ceiling: self.capacity_ceiling,
+            len: self.len,
+            current_generation: self.current_generation,
+            generation_overflow: self.generation_overflow,
+            total_pushed: self.total_pushed,
+            total_evicted: self.total_evicted,
+            on_evict: None,
+        }
+    }
 
-        // Values should be accessible
-        assert_eq!(buffer.get(h1), Some(&1));
+    /// Removes the entries matching the predicate, and returns them with
+    /// the handles they had, from the oldest to the newest
+    ///
+    /// The returned handles are invalid, the other ones stay valid.
+    pub fn drain_where<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> Vec<(Handle<T, G>, T)> {
+        let mut drained = Vec::new();
+        self.filter_entries(|handle, value| {
+            if pred(&value) {
+                drained.push((handle, value));
+                None
+            } else {
+                Some(value)
+            }
+        });
+        drained
+    }
+
+    /// Removes the entries matching the predicate, and returns them in
+    /// an iterator, from the oldest to the newest.
+    ///
+    /// The handles of the removed entries are invalidated, while the other
+    /// handles stay valid. Entries are removed as the iterator is consumed:
+    /// if it's dropped before the end, the remaining entries are kept.
+    pub fn extract_if<'a, F: FnMut(&T) -> bool + 'a>(
+        &'a mut self,
+        mut pred: F,
+    ) -> impl Iterator<Item = T> + 'a {
+        let mut position = 0;
+        std::iter::from_fn(move || {
+            while position < self.len {
+                if pred(&self.values[position]) {
+                    return Some(self.remove_at(position).1);
+                }
+                position += 1;
+            }
+            None
+        })
+    }
+
+    /// Removes the `n` oldest entries, or all entries if there are less,
+    /// and returns them from the oldest to the newest
+    ///
+    /// The handles of the removed entries are invalidated, while the other
+    /// handles stay valid.
+    pub fn drain_oldest(&mut self, n: usize) -> Vec<T> {
+        let n = n.min(self.len);
+        (0..n).map(|_| self.remove_at(0).1).collect()
+    }
+
+    /// Removes the entries sharing their key with a more recent entry,
+    /// so that only the newest entry of each key is kept
+    ///
+    /// The handles of the removed entries are invalidated, while the other
+    /// handles stay valid.
+    pub fn dedup_by_key<K: Eq + Hash, F: FnMut(&T) -> K>(&mut self, mut key: F) {
+        let mut seen = HashSet::new();
+        let mut kept: Vec<bool> = self.values.iter().rev().map(|value| seen.insert(key(value))).collect();
+        self.filter_entries(|_, value| kept.pop().unwrap_or(true).then_some(value));
+    }
+
+    /// Moves the entries to the first slots, in chronological order, so
+    /// that no vacant slot is left, and returns the new handle of each
+    /// entry, keyed by its old handle
+    ///
+    /// As entries move, all existing handles are invalidated: they must
+    /// be replaced using the returned map. Logical indices are kept.
+    pub fn compact_remapping(&mut self) -> HashMap<Handle<T, G>, Handle<T, G>> {
+        // new handles must not be mistaken for old ones
+        self.advance_generation();
+        let generation = self.current_generation;
+        let mut remapping = HashMap::with_capacity(self.len);
+        let mut slots = Vec::with_capacity(self.len);
+        for (position, index) in self.owners.iter_mut().enumerate() {
+            let old = &self.slots[*index];
+            remapping.insert(
+                Handle::new(*index, old.generation),
+                Handle::new(position, generation),
+            );
+            slots.push(Slot {
+                generation,
+                live: true,
+                position,
+                pushed: old.pushed,
+            });
+            *index = position;
+        }
+        self.slots = slots;
+        self.free.clear();
+        self.front = 0;
+        remapping
+    }
+
+    /// Gets a reference to the value associated with the handle
+    ///
+    /// As for `get_mut` and `is_valid`, the handle can be given by value
+    /// or by reference.
+    pub fn get<H: Borrow<Handle<T, G>>>(&self, handle: H) -> Option<&T> {
+        self.position_of(*handle.borrow())
+            .map(|position| &self.values[position])
+    }
+
+    /// Gets a reference to the value associated with the handle, together
+    /// with the generation of its slot
+    ///
+    /// As the handle has to be valid, the generation is the one of the
+    /// handle.
+    pub fn get_with_generation(&self, handle: Handle<T, G>) -> Option<(&T, G)> {
+        self.get(handle).map(|value| (value, handle.generation))
+    }
+
+    /// Returns the handle of the entry in the slot at the given index,
+    /// with its generation, or `None` if the slot is vacant or out
+    /// of range
+    pub fn current_handle_and_generation(&self, index: usize) -> Option<(Handle<T, G>, G)> {
+        let slot = self.slots.get(index)?;
+        slot.live
+            .then(|| (Handle::new(index, slot.generation), slot.generation))
+    }
+
+    /// Gets a mutable reference to the value associated with the handle
+    pub fn get_mut<H: Borrow<Handle<T, G>>>(&mut self, handle: H) -> Option<&mut T> {
+        self.position_of(*handle.borrow())
+            .map(|position| &mut self.values[position])
+    }
+
+    /// Gets mutable references to the values of two distinct entries
+    pub fn get_mut_two(
+        &mut self,
+        a: Handle<T, G>,
+        b: Handle<T, G>,
+    ) -> Result<(&mut T, &mut T), TwoMutError> {
+        let a = self.position_of(a).ok_or(TwoMutError::FirstInvalid)?;
+        let b = self.position_of(b).ok_or(TwoMutError::SecondInvalid)?;
+        if a == b {
+            return Err(TwoMutError::SameSlot);
+        }
+        let (low, high) = (a.min(b), a.max(b));
+        let (older, newer) = self.values.as_mut_slices();
+        let split = older.len();
+        let (low, high) = if high < split {
+            let (left, right) = older.split_at_mut(high);
+            (&mut left[low], &mut right[0])
+        } else if low >= split {
+            let (left, right) = newer.split_at_mut(high - split);
+            (&mut left[low - split], &mut right[0])
+        } else {
+            (&mut older[low], &mut newer[high - split])
+        };
+        if a < b {
+            Ok((low, high))
+        } else {
+            Ok((high, low))
+        }
+    }
+
+    /// Returns the oldest entry with its handle
+    pub fn oldest(&self) -> Option<(Handle<T, G>, &T)> {
+        self.entry_at(0)
+    }
+
+    /// Returns the newest entry with its handle
+    pub fn newest(&self) -> Option<(Handle<T, G>, &T)> {
+        self.entry_at(self.len.checked_sub(1)?)
+    }
+
+    /// Returns the oldest entry with its handle, with mutable access
+    pub fn oldest_mut(&mut self) -> Option<(Handle<T, G>, &mut T)> {
+        self.entry_at_mut(0)
+    }
+
+    /// Returns the newest entry with its handle, with mutable access
+    pub fn newest_mut(&mut self) -> Option<(Handle<T, G>, &mut T)> {
+        self.entry_at_mut(self.len.checked_sub(1)?)
+    }
+
+    /// Gets a reference to the value currently in the slot of the handle,
+    /// even if it's not the value the handle was created for
+    ///
+    /// The returned boolean is true when the value is stale, that is when
+    /// the handle isn't valid anymore. `None` is returned only when the
+    /// slot is out of range or vacant.
+    pub fn get_allowing_stale(&self, handle: Handle<T, G>) -> Option<(&T, bool)> {
+        let slot = self.slots.get(handle.index).filter(|slot| slot.live)?;
+        let value = &self.values[slot.position.wrapping_sub(self.front)];
+        Some((value, slot.generation != handle.generation))
+    }
+
+    /// Gets a reference to the value associated with the handle, without
+    /// checking the handle is valid
+    ///
+    /// # Safety
+    ///
+    /// The handle must be valid, as checked with [`is_valid`](Self::is_valid),
+    /// and the buffer must not have been modified since this check.
+    /// Using an invalid handle is undefined behavior, even if the
+    /// resulting reference is not used.
+    pub unsafe fn get_unchecked(&self, handle: Handle<T, G>) -> &T {
+        let slot = self.slots.get_unchecked(handle.index);
+        self.values
+            .get(slot.position.wrapping_sub(self.front))
+            .unwrap_unchecked()
+    }
+
+    /// Gets a mutable reference to the value associated with the handle,
+    /// without checking the handle is valid
+    ///
+    /// # Safety
+    ///
+    /// The handle must be valid, as checked with [`is_valid`](Self::is_valid),
+    /// and the buffer must not have been modified since this check.
+    /// Using an invalid handle is undefined behavior, even if the
+    /// resulting reference is not used.
+    pub unsafe fn get_unchecked_mut(&mut self, handle: Handle<T, G>) -> &mut T {
+        let slot = self.slots.get_unchecked(handle.index);
+        self.values
+            .get_mut(slot.position.wrapping_sub(self.front))
+            .unwrap_unchecked()
+    }
+
+    /// Checks if a handle is still valid (points to existing data)
+    #[inline]
+    pub fn is_valid<H: Borrow<Handle<T, G>>>(&self, handle: H) -> bool {
+        let handle = handle.borrow();
+        self.slots
+            .get(handle.index)
+            .is_some_and(|slot| slot.holds(handle.generation))
+    }
+
+    /// Returns true if the index of the handle is in range for the capacity
+    /// of this buffer
+    ///
+    /// This is a cheap check to catch handles built from wrong raw parts
+    /// or coming from another buffer: a handle failing it can never be
+    /// valid, while one passing it may still be stale.
+    pub const fn validate_handle_shape(&self, handle: Handle<T, G>) -> bool {
+        // slots beyond the capacity remain after a shrink
+        handle.index < self.max_capacity || handle.index < self.slots.len()
+    }
+
+    /// Returns true if handles created against a buffer of the given
+    /// capacity can be trusted with this buffer
+    ///
+    /// Handles don't hold the capacity, so callers persisting handles must
+    /// also store the capacity of their buffer: after a capacity change (eg
+    /// in a config), the same index and generation may point to another
+    /// entry.
+    pub const fn accepts_handles_from_capacity(&self, capacity: usize) -> bool {
+        capacity == self.max_capacity
+    }
+
+    /// Returns the logical index of a valid handle, that is the number of
+    /// values pushed before its entry, whatever the push method
+    ///
+    /// It's kept by the slot, so it doesn't change when the buffer grows
+    /// or when entries are removed or moved. It increases with the
+    /// chronological order of the entries, except for the ones inserted
+    /// with `push_front` or moved by `rotate`.
+    pub fn logical_index(&self, handle: Handle<T, G>) -> Option<u64> {
+        self.slots
+            .get(handle.index)
+            .filter(|slot| slot.holds(handle.generation))
+            .map(|slot| slot.pushed)
+    }
+
+    /// Returns the current point in the history of the buffer
+    pub fn epoch(&self) -> Epoch {
+        Epoch(self.total_pushed)
+    }
+
+    /// Returns true if the slot of the handle was written by a push
+    /// between the epochs `a` (included) and `b` (excluded)
+    ///
+    /// Only the last write of a slot is known: when it's after `b`, the
+    /// slot is assumed to have been written in the interval too, so the
+    /// answer may be a false positive.
+    pub fn slot_changed_between(&self, handle: Handle<T, G>, a: Epoch, b: Epoch) -> bool {
+        let Some(slot) = self.slots.get(handle.index) else {
+            return false; // never written
+        };
+        b > a && slot.pushed >= a.0
+    }
+
+    /// Returns the handle of the entry whose logical index is given, if
+    /// it's still in the buffer
+    ///
+    /// This is the inverse of [`logical_index`](Self::logical_index).
+    /// It's O(1) when the logical indices of the entries are consecutive,
+    /// which is the case when entries were only pushed, and the
+    /// entries are searched otherwise.
+    pub fn handle_at_logical(&self, logical: u64) -> Option<Handle<T, G>> {
+        let oldest = self.slots[*self.owners.front()?].pushed;
+        let position = logical
+            .checked_sub(oldest)
+            .and_then(|offset| usize::try_from(offset).ok())
+            .filter(|&position| position < self.len);
+        let found = position
+            .filter(|&position| self.slots[self.owners[position]].pushed == logical)
+            .or_else(|| {
+                self.owners
+                    .iter()
+                    .position(|&index| self.slots[index].pushed == logical)
+            })?;
+        Some(self.handle_at(found))
+    }
+
+    /// Returns the handle of the entry the reference points to
+    ///
+    /// This works by pointer identity, and only for references into this
+    /// buffer, as obtained with `get` or `iter`. `None` is returned for any
+    /// other reference, even to an equal value.
+    pub fn handle_of_ref(&self, value: &T) -> Option<Handle<T, G>> {
+        let address = value as *const T as usize;
+        let size = std::mem::size_of::<T>().max(1);
+        let (older, newer) = self.values.as_slices();
+        [(older, 0), (newer, older.len())]
+            .into_iter()
+            .find_map(|(part, offset)| {
+                let i = address.checked_sub(part.as_ptr() as usize)? / size;
+                part.get(i)
+                    .filter(|&v| std::ptr::eq(v, value))
+                    .map(|_| self.handle_at(offset + i))
+            })
+    }
+
+    /// Returns a uniformly chosen random entry with its handle,
+    /// or `None` if the buffer is empty
+    #[cfg(feature = "rand")]
+    pub fn choose<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Option<(Handle<T, G>, &T)> {
+        if self.is_empty() {
+            return None;
+        }
+        self.entry_at(rng.random_range(0..self.len))
+    }
+
+    /// Returns the handles which are no longer valid, in the given order
+    pub fn filter_invalid(&self, handles: impl IntoIterator<Item = Handle<T, G>>) -> Vec<Handle<T, G>> {
+        handles
+            .into_iter()
+            .filter(|&handle| !self.is_valid(handle))
+            .collect()
+    }
+
+    /// Splits the handles into the valid ones and the stale ones, keeping
+    /// their order
+    pub fn partition_valid(
+        &self,
+        handles: impl IntoIterator<Item = Handle<T, G>>,
+    ) -> Partition<T, G> {
+        handles.into_iter().partition(|&handle| self.is_valid(handle))
+    }
+
+    /// Returns an iterator over the valid handles among the given ones,
+    /// with their values, in the given order
+    pub fn resolve_all<'a>(
+        &'a self,
+        handles: impl IntoIterator<Item = Handle<T, G>> + 'a,
+    ) -> impl Iterator<Item = (Handle<T, G>, &'a T)> {
+        handles
+            .into_iter()
+            .filter_map(|handle| self.get(handle).map(|value| (handle, value)))
+    }
+
+    /// Returns the validity of the handles as a packed bitset
+    ///
+    /// The validity of `handles[i]` is the bit `i % 64` (counting from the
+    /// least significant one) of the word `i / 64`. Unused bits of the
+    /// last word are 0.
+    pub fn validity_bitset(&self, handles: &[Handle<T, G>]) -> Vec<u64> {
+        let mut bitset = vec![0; handles.len().div_ceil(64)];
+        for (i, &handle) in handles.iter().enumerate() {
+            if self.is_valid(handle) {
+                bitset[i / 64] |= 1 << (i % 64);
+            }
+        }
+        bitset
+    }
+
+    /// Returns true if the buffer contains an entry equal to the given value
+    ///
+    /// Only the live entries are checked, values which were overwritten
+    /// or removed aren't kept in the buffer.
+    pub fn contains(&self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.values.contains(value)
+    }
+
+    /// Returns the number of entries matching the predicate
+    pub fn count_where<F: FnMut(&T) -> bool>(&self, mut pred: F) -> usize {
+        self.values().filter(|value| pred(value)).count()
+    }
+
+    /// Returns true if at least one entry matches the predicate
+    ///
+    /// Entries are checked from the oldest to the newest, until one matches.
+    pub fn any<F: FnMut(&T) -> bool>(&self, pred: F) -> bool {
+        self.values().any(pred)
+    }
+
+    /// Returns true if all entries match the predicate, which is the case
+    /// when the buffer is empty
+    ///
+    /// Entries are checked from the oldest to the newest, until one doesn't
+    /// match.
+    pub fn all<F: FnMut(&T) -> bool>(&self, pred: F) -> bool {
+        self.values().all(pred)
+    }
+
+    /// Returns an iterator over all entries with their handles,
+    /// from the oldest to the newest
+    ///
+    /// The iterator can be reversed to get the newest entries first.
+    pub fn iter(&self) -> Iter<'_, T, G> {
+        self.iter_positions(0..self.len)
+    }
+
+    /// Returns an iterator over all entries, from the oldest to the newest
+    pub fn values(&self) -> Values<'_, T, G> {
+        Values::new(self.iter())
+    }
+
+    /// Returns an iterator over all valid handles, from the oldest to the
+    /// newest
+    pub fn handles(&self) -> Handles<'_, T, G> {
+        Handles::new(self.iter())
+    }
+
+    /// Returns an iterator over all entries with their handles, in the
+    /// order of the values in memory
+    ///
+    /// This order depends on where the storage wrapped and has no meaning
+    /// for the buffer: use [`iter`](Self::iter) for the chronological order.
+    pub fn iter_unordered(&self) -> Iter<'_, T, G> {
+        Iter::physical(&self.values, &self.owners, &self.slots)
+    }
+
+    /// Returns an iterator over all entries, in the order of the values
+    /// in memory
+    pub fn values_unordered(&self) -> Values<'_, T, G> {
+        Values::new(self.iter_unordered())
+    }
+
+    /// Returns an iterator over all valid handles, in the order of the
+    /// values in memory
+    pub fn handles_unordered(&self) -> Handles<'_, T, G> {
+        Handles::new(self.iter_unordered())
+    }
+
+    /// Returns an iterator over the handles of the entries matching the
+    /// predicate, from the oldest to the newest
+    pub fn handles_where<'a, F: FnMut(&T) -> bool + 'a>(
+        &'a self,
+        mut pred: F,
+    ) -> impl DoubleEndedIterator<Item = Handle<T, G>> + FusedIterator + 'a {
+        self.iter()
+            .filter_map(move |(handle, value)| pred(value).then_some(handle))
+    }
+
+    /// Returns an iterator over all entries with their handles,
+    /// from the oldest to the newest, same as [`iter`](Self::iter)
+    pub fn iter_chronological(&self) -> Iter<'_, T, G> {
+        self.iter()
+    }
+
+    /// Returns an iterator over all entries with their handles and their
+    /// position, from the oldest (at position 0) to the newest
+    pub fn enumerate_chronological(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (usize, Handle<T, G>, &T)> + ExactSizeIterator + FusedIterator
+    {
+        self.iter_chronological()
+            .enumerate()
+            .map(|(position, (handle, value))| (position, handle, value))
+    }
+
+    /// Returns an iterator over the entries more recent than the entry
+    /// of the handle, from the oldest to the newest
+    ///
+    /// This makes it possible to paginate over the buffer by giving the
+    /// last handle of the previous page. If the handle is stale, all
+    /// entries are returned, as by [`iter_chronological`](Self::iter_chronological).
+    pub fn iter_after(&self, handle: Handle<T, G>) -> Iter<'_, T, G> {
+        let start = self.position_of(handle).map_or(0, |position| position + 1);
+        self.iter_positions(start..self.len)
+    }
+
+    /// Returns the values older than the entry of the handle and the
+    /// ones newer than it, both from the oldest to the newest, or `None`
+    /// if the handle isn't valid
+    ///
+    /// The entry of the handle is in neither part.
+    pub fn split_around(&self, handle: Handle<T, G>) -> Option<(Vec<&T>, Vec<&T>)> {
+        let position = self.position_of(handle)?;
+        Some((
+            self.values.range(..position).collect(),
+            self.values.range(position + 1..).collect(),
+        ))
+    }
+
+    /// Returns an iterator over all entries with their handles,
+    /// from the newest to the oldest
+    pub fn iter_rev(&self) -> Rev<Iter<'_, T, G>> {
+        self.iter().rev()
+    }
+
+    /// Returns an iterator over the entries with their handles, in
+    /// chronological order, whose positions are in the range
+    ///
+    /// Position 0 is the oldest entry. The range is clamped to `len()`.
+    pub fn range_chronological(&self, range: Range<usize>) -> Iter<'_, T, G> {
+        let end = range.end.min(self.len);
+        self.iter_positions(range.start.min(end)..end)
+    }
+
+    /// Copies the entries, from the oldest to the newest, into the slice,
+    /// and returns the number of copied entries
+    ///
+    /// If the slice is shorter than `len()`, only the oldest entries are
+    /// copied. Nothing is allocated.
+    pub fn copy_chronological_into(&self, dst: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let mut count = 0;
+        for (d, &value) in dst.iter_mut().zip(&self.values) {
+            *d = value;
+            count += 1;
+        }
+        count
+    }
+
+    /// Replaces the content of the vector with clones of the entries, from
+    /// the oldest to the newest, and returns them as a contiguous slice
+    ///
+    /// As the storage of the buffer may wrap, its values aren't always
+    /// contiguous. The returned slice has `len()` elements and its `as_ptr`
+    /// can be passed to C. Reusing the vector avoids allocating once its
+    /// capacity is large enough.
+    pub fn make_contiguous_in<'v>(&self, dst: &'v mut Vec<T>) -> &'v mut [T]
+    where
+        T: Clone,
+    {
+        dst.clear();
+        dst.extend(self.values.iter().cloned());
+        dst
+    }
+
+    /// Returns all entries, from the oldest to the newest, as a slice, if
+    /// they're contiguous in memory
+    ///
+    /// This is the case unless the storage wraps, which can be fixed with
+    /// [`make_contiguous`](Self::make_contiguous).
+    pub fn as_contiguous_slice(&self) -> Option<&[T]> {
+        self.get_slice_if_contiguous(0..self.len)
+    }
+
+    /// Returns the entries whose positions are in the range, from the
+    /// oldest to the newest, as a slice, if they're contiguous in memory
+    ///
+    /// Position 0 is the oldest entry. The range is clamped to `len()`.
+    pub fn get_slice_if_contiguous(&self, range: Range<usize>) -> Option<&[T]> {
+        let end = range.end.min(self.len);
+        let start = range.start.min(end);
+        let (older, newer) = self.values.as_slices();
+        let split = older.len();
+        if end <= split {
+            Some(&older[start..end])
+        } else if start >= split {
+            Some(&newer[start - split..end - split])
+        } else {
+            None
+        }
+    }
+
+    /// Moves the values in memory so that they're contiguous, and returns
+    /// them from the oldest to the newest
+    ///
+    /// All handles stay valid, and nothing is moved if the values were
+    /// already contiguous.
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        self.values.make_contiguous()
+    }
+
+    /// Returns clones of the `n` newest values (or less if there aren't
+    /// enough entries), from the oldest to the newest
+    ///
+    /// This makes it possible to take a snapshot with a single call, eg
+    /// while holding a lock.
+    pub fn newest_n_cloned(&self, n: usize) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let n = n.min(self.len);
+        self.values.range(self.len - n..).cloned().collect()
+    }
+
+    /// Returns all valid handles, from the oldest to the newest
+    pub fn handles_chronological(&self) -> Vec<Handle<T, G>> {
+        self.handles().collect()
+    }
+
+    /// Returns an iterator over all valid handles, from the newest to
+    /// the oldest
+    pub fn handles_rev(&self) -> Rev<Handles<'_, T, G>> {
+        self.handles().rev()
+    }
+
+    /// Returns the entries with their handles, sorted by the key
+    ///
+    /// Entries with equal keys are kept in chronological order.
+    pub fn sorted_by<K: Ord, F: FnMut(&T) -> K>(&self, mut key: F) -> Vec<(Handle<T, G>, &T)> {
+        let mut entries: Vec<_> = self.iter_chronological().collect();
+        entries.sort_by_cached_key(|(_, value)| key(value));
+        entries
+    }
+
+    /// Returns an iterator over groups of consecutive entries sharing
+    /// the same key, in chronological order (oldest first)
+    pub fn chunk_by<K: PartialEq, F: FnMut(&T) -> K>(
+        &self,
+        mut key: F,
+    ) -> impl Iterator<Item = Vec<&T>> {
+        let mut values = self.values.iter().peekable();
+        std::iter::from_fn(move || {
+            let first = values.next()?;
+            let first_key = key(first);
+            let mut chunk = vec![first];
+            while let Some(value) = values.next_if(|value| key(value) == first_key) {
+                chunk.push(value);
+            }
+            Some(chunk)
+        })
+    }
+
+    /// Returns the generation the slot was last written at, or `None`
+    /// if the slot isn't allocated
+    pub(crate) fn slot_generation(&self, index: usize) -> Option<G> {
+        self.slots.get(index).map(|slot| slot.generation)
+    }
+
+    /// Returns the position of the entry of the handle, from the oldest
+    /// one, if the handle is valid
+    fn position_of(&self, handle: Handle<T, G>) -> Option<usize> {
+        self.slots
+            .get(handle.index)
+            .filter(|slot| slot.holds(handle.generation))
+            .map(|slot| slot.position.wrapping_sub(self.front))
+    }
+
+    /// Returns the handle of the entry at the given position
+    fn handle_at(&self, position: usize) -> Handle<T, G> {
+        let index = self.owners[position];
+        Handle::new(index, self.slots[index].generation)
+    }
+
+    /// Removes the entry at the given position, and returns it with the
+    /// handle it had, leaving its slot vacant
+    ///
+    /// The positions of the entries on the shorter side are updated.
+    fn remove_at(&mut self, position: usize) -> Evicted<T, G> {
+        if position < self.len / 2 {
+            for &index in self.owners.range(..position) {
+                let slot = &mut self.slots[index];
+                slot.position = slot.position.wrapping_add(1);
+            }
+            self.front = self.front.wrapping_add(1);
+        } else {
+            for &index in self.owners.range(position + 1..) {
+                let slot = &mut self.slots[index];
+                slot.position = slot.position.wrapping_sub(1);
+            }
+        }
+        let index = self
+            .owners
+            .remove(position)
+            .expect("the position should be in range");
+        let value = self
+            .values
+            .remove(position)
+            .expect("the position should be in range");
+        self.len -= 1;
+        self.slots[index].live = false;
+        self.free.push_back(index);
+        (Handle::new(index, self.slots[index].generation), value)
+    }
+
+    /// Calls the function with each entry, from the oldest to the newest,
+    /// keeping the entries for which it returns the value back
+    ///
+    /// This is O(n) and doesn't allocate.
+    fn filter_entries<F: FnMut(Handle<T, G>, T) -> Option<T>>(&mut self, mut f: F) {
+        let mut kept = 0;
+        for _ in 0..self.len {
+            let (Some(value), Some(index)) = (self.values.pop_front(), self.owners.pop_front())
+            else {
+                unreachable!("there are len values and owners");
+            };
+            let slot = &mut self.slots[index];
+            match f(Handle::new(index, slot.generation), value) {
+                Some(value) => {
+                    // the kept entries are rotated to the back, in order
+                    slot.position = kept;
+                    kept += 1;
+                    self.values.push_back(value);
+                    self.owners.push_back(index);
+                }
+                None => {
+                    slot.live = false;
+                    self.free.push_back(index);
+                }
+            }
+        }
+        self.front = 0;
+        self.len = kept;
+    }
+
+    /// Sets the positions of all slots from the order of the entries
+    fn renumber(&mut self) {
+        self.front = 0;
+        for (position, &index) in self.owners.iter().enumerate() {
+            self.slots[index].position = position;
+        }
+    }
+
+    /// Returns an iterator over the entries whose positions are in the
+    /// range, which must be within `0..len`
+    fn iter_positions(&self, range: Range<usize>) -> Iter<'_, T, G> {
+        Iter::chronological(&self.values, &self.owners, &self.slots, range)
+    }
+
+    /// Returns a `Debug` view of the entries from the oldest to the newest,
+    /// each one with its age (0 for the newest)
+    ///
+    /// The `Debug` implementation of the buffer itself shows the raw state.
+    pub fn debug_chronological(&self) -> impl fmt::Debug + '_
+    where
+        T: fmt::Debug,
+    {
+        struct Chronological<'a, T, G: Generation>(&'a GenerationalBuffer<T, G>);
+        impl<T: fmt::Debug, G: Generation> fmt::Debug for Chronological<'_, T, G> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let len = self.0.len;
+                f.debug_map()
+                    .entries(
+                        self.0
+                            .values
+                            .iter()
+                            .enumerate()
+                            .map(|(i, value)| (len - 1 - i, value)),
+                    )
+                    .finish()
+            }
+        }
+        Chronological(self)
+    }
+
+    /// Returns the entry at the given position, with its handle
+    fn entry_at(&self, position: usize) -> Option<(Handle<T, G>, &T)> {
+        let value = self.values.get(position)?;
+        Some((self.handle_at(position), value))
+    }
+
+    /// Returns the entry at the given position, with its handle and
+    /// mutable access
+    fn entry_at_mut(&mut self, position: usize) -> Option<(Handle<T, G>, &mut T)> {
+        let handle = (position < self.len).then(|| self.handle_at(position))?;
+        self.values.get_mut(position).map(|value| (handle, value))
+    }
+}
+
+/// Helpers for tests, also available to dependent crates with
+/// the `testing` feature
+#[cfg(any(test, feature = "testing"))]
+impl<T, G: Generation> GenerationalBuffer<T, G> {
+    /// Checks the internal invariants of the buffer, returning a description
+    /// of the first violation found
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.len > self.max_capacity {
+            return Err(format!(
+                "len {} exceeds the capacity {}",
+                self.len, self.max_capacity
+            ));
+        }
+        if self.values.len() != self.len || self.owners.len() != self.len {
+            return Err(format!(
+                "{} values and {} owners but len is {}",
+                self.values.len(),
+                self.owners.len(),
+                self.len
+            ));
+        }
+        for (position, &index) in self.owners.iter().enumerate() {
+            let Some(slot) = self.slots.get(index).filter(|slot| slot.live) else {
+                return Err(format!("the slot {index} of the entry {position} isn't live"));
+            };
+            if slot.position.wrapping_sub(self.front) != position {
+                return Err(format!(
+                    "the slot {index} of the entry {position} is at the position {}",
+                    slot.position.wrapping_sub(self.front),
+                ));
+            }
+        }
+        let live = self.slots.iter().filter(|slot| slot.live).count();
+        if live != self.len {
+            return Err(format!("{live} live slots but len is {}", self.len));
+        }
+        let free: HashSet<usize> = self.free.iter().copied().collect();
+        if free.len() != self.free.len()
+            || free.len() != self.slots.len() - live
+            || free.iter().any(|&index| self.slots.get(index).is_none_or(|slot| slot.live))
+        {
+            return Err(format!("the vacant slots aren't {:?}", self.free));
+        }
+        for (i, slot) in self.slots.iter().enumerate() {
+            // slots are written at most at the current generation
+            let distance = slot.generation.wrapping_distance(self.current_generation);
+            if distance < 0 {
+                return Err(format!(
+                    "slot {i} has generation {:?} with current generation {:?}",
+                    slot.generation, self.current_generation,
+                ));
+            }
+        }
+        if let Some(handle) = self.handles().find(|&handle| !self.is_valid(handle)) {
+            return Err(format!(
+                "handle to slot {} with generation {:?} from handles() isn't valid",
+                handle.index, handle.generation,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Panics with a descriptive message if the handle isn't valid or
+    /// doesn't resolve to the expected value
+    #[track_caller]
+    pub fn debug_assert_resolves(&self, handle: Handle<T, G>, expected: &T)
+    where
+        T: PartialEq + fmt::Debug,
+    {
+        match self.get_allowing_stale(handle) {
+            Some((value, false)) => {
+                assert!(
+                    value == expected,
+                    "handle {handle:?} resolves to {value:?} instead of {expected:?}",
+                );
+            }
+            Some((value, true)) => {
+                panic!(
+                    "handle {handle:?} is stale (its slot now holds {value:?}), expected {expected:?}",
+                );
+            }
+            None => {
+                panic!("handle {handle:?} points to no entry, expected {expected:?}");
+            }
+        }
+    }
+
+    /// Returns a diagram of the slots, like `[4@1 5@1|2@0 3@0]`, where
+    /// each slot shows its value (`_` when vacant, `.` when not allocated)
+    /// and its generation, and the bar is before the slot the next push
+    /// writes
+    pub fn layout_string(&self) -> String
+    where
+        T: fmt::Debug,
+    {
+        let capacity = if self.is_full() && self.max_capacity < self.capacity_ceiling {
+            self.max_capacity.saturating_mul(2).min(self.capacity_ceiling)
+        } else {
+            self.max_capacity
+        };
+        let next = if self.slots.len() < capacity {
+            Some(self.slots.len())
+        } else {
+            // when full, the slot of the oldest entry is freed last
+            self.free.front().or(self.owners.front()).copied()
+        };
+        let mut layout = String::from("[");
+        for index in 0..self.max_capacity.max(self.slots.len()) {
+            if Some(index) == next {
+                layout.push('|');
+            } else if index > 0 {
+                layout.push(' ');
+            }
+            match self.slots.get(index) {
+                Some(slot) if slot.live => {
+                    let value = &self.values[slot.position.wrapping_sub(self.front)];
+                    layout.push_str(&format!("{value:?}@{}", slot.generation.to_u64()));
+                }
+                Some(slot) => {
+                    layout.push_str(&format!("_@{}", slot.generation.to_u64()));
+                }
+                None => layout.push('.'),
+            }
+        }
+        layout.push(']');
+        layout
+    }
+}
+
+/// Builds consistent buffers, by pushing arbitrary values into a buffer
+/// of arbitrary capacity and starting generation
+#[cfg(feature = "arbitrary")]
+impl<'a, T, G> arbitrary::Arbitrary<'a> for GenerationalBuffer<T, G>
+where
+    T: arbitrary::Arbitrary<'a>,
+    G: Generation + arbitrary::Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let max_capacity: usize = u.int_in_range(1..=64)?;
+        let mut buffer = Self::with_generation_width(max_capacity);
+        buffer.current_generation = G::arbitrary(u)?;
+        for value in u.arbitrary_iter()? {
+            buffer.push(value?);
+        }
+        Ok(buffer)
+    }
+}
+
+impl<T: fmt::Debug, G: Generation> fmt::Debug for GenerationalBuffer<T, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GenerationalBuffer")
+            .field("capacity", &self.capacity())
+            .field("capacity_ceiling", &self.capacity_ceiling)
+            .field("len", &self.len())
+            .field("current_generation", &self.current_generation)
+            .field("generation_overflow", &self.generation_overflow)
+            .field("total_pushed", &self.total_pushed)
+            .field("total_evicted", &self.total_evicted)
+            .field("values", &self.values)
+            .field("owners", &self.owners)
+            .field("slots", &self.slots)
+            .field("free", &self.free)
+            .field("on_evict", &self.on_evict.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_operations() {
+        let mut buffer = GenerationalBuffer::new(3);
+
+        // Insert some values
+        let h1 = buffer.push(10);
+        let h2 = buffer.push(20);
+        let h3 = buffer.push(30);
+
+        // Check values
+        assert_eq!(buffer.get(h1), Some(&10));
+        assert_eq!(buffer.get(h2), Some(&20));
+        assert_eq!(buffer.get(h3), Some(&30));
+        assert_eq!(buffer.len(), 3);
+        assert!(buffer.is_full());
+    }
+
+    #[test]
+    fn test_circular_wrapping() {
+        let mut buffer = GenerationalBuffer::new(2);
+
+        // Fill the buffer
+        let h1 = buffer.push(10);
+        let h2 = buffer.push(20);
+
+        // Wrap around - this should invalidate h1
+        let h3 = buffer.push(30);
+
+        // h1 should now be invalid due to generation mismatch
+        assert_eq!(buffer.get(h1), None);
+        assert_eq!(buffer.get(h2), Some(&20));
+        assert_eq!(buffer.get(h3), Some(&30));
+        assert!(!buffer.is_valid(h1));
+        assert!(buffer.is_valid(h2));
+        assert!(buffer.is_valid(h3));
+        assert_eq!(buffer.len(), 2);
+
+        // Do one more turn
+        let h4 = buffer.push(40); // This should overwrite h2
+        let h5 = buffer.push(50); // This should overwrite h3
+        assert_eq!(buffer.get(h4), Some(&40));
+        assert_eq!(buffer.get(h5), Some(&50));
+        assert!(!buffer.is_valid(h2)); // h2 should be invalid now
+        assert!(!buffer.is_valid(h3)); // h3 should be invalid now
+
+        // Clear the buffer
+        buffer.clear();
+        assert!(buffer.is_empty());
+        assert!(!buffer.is_valid(h4)); // h4 should be invalid now
+        let h6 = buffer.push(60); // New handle after clear
+        assert_eq!(buffer.get(h6), Some(&60));
+    }
+
+    #[test]
+    fn test_generation_calculation() {
+        let mut buffer = GenerationalBuffer::new(3);
+
+        // Fill buffer multiple times
+        let handles: Vec<_> = (0..10).map(|i| buffer.push(i)).collect();
+
+        // Only the last 3 handles should be valid
+        for (i, &handle) in handles.iter().enumerate() {
+            if i < 7 {
+                assert!(!buffer.is_valid(handle), "Handle {i} should be invalid");
+            } else {
+                assert!(buffer.is_valid(handle), "Handle {i} should be valid");
+            }
+        }
+    }
+
+    #[test]
+    fn test_iterator() {
+        let mut buffer = GenerationalBuffer::new(3);
+
+        buffer.push(10);
+        buffer.push(20);
+        buffer.push(30);
+        buffer.push(40);
+        buffer.push(50);
+        buffer.push(60);
+        buffer.push(70);
+        buffer.push(80);
+
+        // entries are given from the oldest to the newest
+        let values: Vec<i32> = buffer.values().cloned().collect();
+        assert_eq!(values, vec![60, 70, 80]);
+
+        let handles: Vec<_> = buffer.handles().collect();
+        assert_eq!(handles.len(), 3);
+        let values: Vec<i32> = handles
+            .iter()
+            .map(|&h| *buffer.get(h).unwrap())
+            .collect();
+        assert_eq!(values, vec![60, 70, 80]);
+
+        // Verify all handles are valid
+        for handle in handles {
+            assert!(buffer.is_valid(handle));
+        }
+    }
+
+    #[test]
+    fn test_growing_buffer() {
+        let mut buffer = GenerationalBuffer::new(5);
+
+        // Add elements one by one
+        let h1 = buffer.push(1);
+        let h2 = buffer.push(2);
+        let h3 = buffer.push(3);
+
+        assert_eq!(buffer.len(), 3);
+        assert!(!buffer.is_full());
+
+        // All handles should be valid
+        assert!(buffer.is_valid(h1));
+        assert!(buffer.is_valid(h2));
+        assert!(buffer.is_valid(h3));
+
+        // Values should be accessible
+        assert_eq!(buffer.get(h1), Some(&1));
+        assert_eq!(buffer.get(h2), Some(&2));
+        assert_eq!(buffer.get(h3), Some(&3));
+    }
+
+    #[test]
+    fn test_generation_distance() {
+        let mut buffer = GenerationalBuffer::new(2);
+        let h1 = buffer.push(1);
+        let h2 = buffer.push(2);
+        let h3 = buffer.push(3);
+
+        // same generation
+        assert_eq!(Handle::generation_distance(h1, h2), 0);
+        assert_eq!(Handle::generation_distance(h1, h1), 0);
+
+        // adjacent generations
+        assert_eq!(Handle::generation_distance(h1, h3), 1);
+        assert_eq!(Handle::generation_distance(h3, h1), -1);
+
+        // around the u32 wrap
+        let before: Handle<i32> = Handle::new(0, u32::MAX);
+        let after: Handle<i32> = Handle::new(0, 1);
+        assert_eq!(Handle::generation_distance(before, after), 2);
+        assert_eq!(Handle::generation_distance(after, before), -2);
+    }
+
+    #[test]
+    fn test_reserve_and_replace() {
+        let mut buffer: GenerationalBuffer<String> = GenerationalBuffer::new(3);
+        let h1 = buffer.push("a".to_string());
+        let h2 = buffer.reserve();
+        assert_eq!(buffer.get(h2), Some(&String::new()));
+        assert_eq!(buffer.replace(h2, "b".to_string()), Some(String::new()));
+        assert!(buffer.is_valid(h2));
+        assert_eq!(buffer.get(h2).map(String::as_str), Some("b"));
+        assert_eq!(buffer.get(h1).map(String::as_str), Some("a"));
+
+        // A reserved slot can be evicted before being filled
+        let h3 = buffer.reserve();
+        buffer.push("c".to_string());
+        buffer.push("d".to_string());
+        buffer.push("e".to_string());
+        assert_eq!(buffer.replace(h3, "late".to_string()), None);
+        assert_eq!(buffer.len(), 3);
+    }
+
+    // Also meant to be checked with `cargo miri test`
+    #[test]
+    fn test_get_unchecked() {
+        let mut buffer = GenerationalBuffer::new(2);
+        buffer.push(1);
+        let h2 = buffer.push(2);
+        let h3 = buffer.push(3);
+        assert!(buffer.is_valid(h2));
+        assert!(buffer.is_valid(h3));
+        unsafe {
+            assert_eq!(*buffer.get_unchecked(h2), 2);
+            *buffer.get_unchecked_mut(h3) += 10;
+            assert_eq!(*buffer.get_unchecked(h3), 13);
+        }
+        assert_eq!(buffer.get(h3), Some(&13));
+    }
+
+    #[test]
+    fn test_contains() {
+        let mut buffer = GenerationalBuffer::new(3);
+        for i in 1..=5 {
+            buffer.push(i);
+        }
+        assert!(buffer.contains(&3));
+        assert!(buffer.contains(&5));
+        assert!(!buffer.contains(&2)); // overwritten
+        assert!(!buffer.contains(&6));
+    }
+
+    #[test]
+    fn test_chunk_by() {
+        let mut buffer = GenerationalBuffer::new(4);
+        for c in ['x', 'a', 'a', 'b', 'a'] {
+            buffer.push(c);
+        }
+        let chunks: Vec<Vec<&char>> = buffer.chunk_by(|&c| c).collect();
+        assert_eq!(chunks, vec![vec![&'a', &'a'], vec![&'b'], vec![&'a']]);
+
+        // grouping by a derived key, across the wrap seam
+        let mut buffer = GenerationalBuffer::new(3);
+        for i in [1, 2, 4, 6, 7] {
+            buffer.push(i);
+        }
+        let chunks: Vec<Vec<&i32>> = buffer.chunk_by(|i| i % 2).collect();
+        assert_eq!(chunks, vec![vec![&4, &6], vec![&7]]);
+    }
+
+    #[test]
+    fn test_chronological_order() {
+        let mut buffer = GenerationalBuffer::new(4);
+        for i in 0..6 {
+            buffer.push(i);
+        }
+        let values: Vec<i32> = buffer.iter_chronological().map(|(_, &v)| v).collect();
+        assert_eq!(values, vec![2, 3, 4, 5]);
+        let handles = buffer.handles_chronological();
+        let expected: Vec<_> = buffer.iter_chronological().map(|(h, _)| h).collect();
+        assert_eq!(handles, expected);
+        for (handle, value) in handles.iter().zip(values) {
+            assert_eq!(buffer.get(*handle), Some(&value));
+        }
+    }
+
+    #[test]
+    fn test_merge_newest_from() {
+        let mut central = GenerationalBuffer::new(4);
+        central.push(1);
+        central.push(2);
+        let mut local = GenerationalBuffer::new(3);
+        let stale = local.push(10);
+        for i in 11..15 {
+            local.push(i);
+        }
+        central.merge_newest_from(&mut local);
+        let values: Vec<i32> = central.iter_chronological().map(|(_, &v)| v).collect();
+        assert_eq!(values, vec![2, 12, 13, 14]);
+        assert!(local.is_empty());
+        assert!(!local.is_valid(stale));
+        let h = local.push(20);
+        assert_eq!(local.get(h), Some(&20));
+    }
+
+    #[test]
+    fn test_exact_size_iterators() {
+        let mut buffer = GenerationalBuffer::new(4);
+        assert_eq!(buffer.values().len(), 0);
+        for i in 0..6 {
+            buffer.push(i);
+            assert_eq!(buffer.values().len(), buffer.len());
+            assert_eq!(buffer.iter().len(), buffer.len());
+            assert_eq!(buffer.handles().len(), buffer.len());
+        }
+        let mut values = buffer.values();
+        values.next();
+        assert_eq!(values.len(), 3);
+    }
+
+    #[test]
+    fn test_capacity_newtype() {
+        let a: GenerationalBuffer<u8> = GenerationalBuffer::new(Capacity(16));
+        let b: GenerationalBuffer<u8> = GenerationalBuffer::new(16);
+        assert_eq!(a.capacity(), 16);
+        assert_eq!(b.capacity(), 16);
+        let c: GenerationalBuffer<u8> = GenerationalBuffer::new(Capacity(0));
+        assert_eq!(c.capacity(), 1);
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let mut buffer = GenerationalBuffer::new(5);
+        let handles: Vec<_> = (0..7).map(|i| buffer.push(i)).collect();
+        let evens: Vec<i32> = buffer.extract_if(|v| v % 2 == 0).collect();
+        assert_eq!(evens, vec![2, 4, 6]);
+        assert_eq!(buffer.len(), 2);
+        assert!(!buffer.is_full());
+        for (i, &handle) in handles.iter().enumerate() {
+            let live = i >= 2 && i % 2 == 1;
+            assert_eq!(buffer.is_valid(handle), live, "handle {i}");
+        }
+        assert_eq!(buffer.get(handles[3]), Some(&3));
+        assert_eq!(buffer.get(handles[5]), Some(&5));
+        assert_eq!(buffer.values().len(), 2);
+
+        // vacant slots are reused when the ring comes back to them
+        let h7 = buffer.push(7);
+        assert_eq!(buffer.len(), 3);
+        let values: Vec<i32> = buffer.iter_chronological().map(|(_, &v)| v).collect();
+        assert_eq!(values, vec![3, 5, 7]);
+        assert!(buffer.is_valid(h7));
+        assert!(buffer.is_valid(handles[3]));
+    }
+
+    #[test]
+    fn test_map_and_remap_handle() {
+        let mut buffer = GenerationalBuffer::new(3);
+        let handles: Vec<_> = (1..=5).map(|i| buffer.push(i)).collect();
+        let mapped = buffer.map(|i| format!("#{i}"));
+        let h5: Handle<String> = Handle::remap_handle(handles[4]);
+        assert_eq!(mapped.get(h5).map(String::as_str), Some("#5"));
+        let h1: Handle<String> = Handle::remap_handle(handles[0]);
+        assert_eq!(mapped.get(h1), None);
+        assert_eq!(mapped.len(), 3);
+    }
+
+    #[test]
+    fn test_filter_invalid() {
+        let mut buffer = GenerationalBuffer::new(3);
+        let handles: Vec<_> = (0..5).map(|i| buffer.push(i)).collect();
+        let invalid = buffer.filter_invalid(handles.iter().copied().rev());
+        assert_eq!(invalid, vec![handles[1], handles[0]]);
+        buffer.clear();
+        assert_eq!(buffer.filter_invalid(handles.clone()), handles);
+    }
+
+    #[test]
+    fn test_push_tracking() {
+        let mut buffer = GenerationalBuffer::new(2);
+        let (h1, evicted) = buffer.push_tracking(1);
+        assert_eq!(evicted, None);
+        let (h2, evicted) = buffer.push_tracking(2);
+        assert_eq!(evicted, None);
+        let (h3, evicted) = buffer.push_tracking(3);
+        assert_eq!(evicted, Some(h1));
+        let (_, evicted) = buffer.push_tracking(4);
+        assert_eq!(evicted, Some(h2));
+        let (_, evicted) = buffer.push_tracking(5);
+        assert_eq!(evicted, Some(h3));
+        assert!(!buffer.is_valid(h3));
+    }
+
+    #[test]
+    fn test_dedup_by_key() {
+        let mut buffer = GenerationalBuffer::new(6);
+        let handles: Vec<_> = [("a", 1), ("b", 2), ("a", 3), ("c", 4), ("b", 5)]
+            .into_iter()
+            .map(|entry| buffer.push(entry))
+            .collect();
+        buffer.dedup_by_key(|&(key, _)| key);
+        assert_eq!(buffer.len(), 3);
+        let values: Vec<_> = buffer.iter_chronological().map(|(_, &v)| v).collect();
+        assert_eq!(values, vec![("a", 3), ("c", 4), ("b", 5)]);
+        let valid: Vec<_> = handles.iter().map(|&h| buffer.is_valid(h)).collect();
+        assert_eq!(valid, vec![false, false, true, true, true]);
+    }
+
+    #[test]
+    fn test_handle_of_ref() {
+        let mut buffer = GenerationalBuffer::new(3);
+        for i in 0..5 {
+            buffer.push(i);
+        }
+        for (handle, value) in buffer.iter() {
+            assert_eq!(buffer.handle_of_ref(value), Some(handle));
+        }
+        let outside = 3;
+        assert_eq!(buffer.handle_of_ref(&outside), None);
+    }
+
+    #[test]
+    fn test_on_evict() {
+        use std::sync::{
+            Arc,
+            Mutex,
+        };
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&evicted);
+        let mut buffer = GenerationalBuffer::new(2)
+            .on_evict(Box::new(move |value| sink.lock().unwrap().push(value)));
+        for i in 0..5 {
+            buffer.push(i);
+        }
+        buffer.push_tracking(5);
+        assert_eq!(*evicted.lock().unwrap(), vec![0, 1, 2, 3]);
+        let values: Vec<i32> = buffer.iter_chronological().map(|(_, &v)| v).collect();
+        assert_eq!(values, vec![4, 5]);
+    }
+
+    #[test]
+    fn test_on_evict_before_write() {
+        // the callback doesn't have to be Sync
+        let count = std::cell::Cell::new(0);
+        let mut buffer = GenerationalBuffer::new(2).on_evict(Box::new(move |value| {
+            count.set(count.get() + 1);
+            assert!(value != 1, "evicting 1");
+        }));
+        let handles = [buffer.push(0), buffer.push(1)];
+        buffer.push(2);
+        let pushing = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| buffer.push(3)));
+        assert!(pushing.is_err());
+        // the evicted entry was removed, and the new value wasn't written
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![2]);
+        assert!(!buffer.is_valid(handles[1]));
+        buffer.check_invariants().unwrap();
+        buffer.push(4);
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![2, 4]);
+    }
+
+    #[test]
+    fn test_get_allowing_stale() {
+        let mut buffer = GenerationalBuffer::new(2);
+        let h1 = buffer.push(1);
+        assert_eq!(buffer.get_allowing_stale(h1), Some((&1, false)));
+        buffer.push(2);
+        buffer.push(3);
+        assert_eq!(buffer.get(h1), None);
+        assert_eq!(buffer.get_allowing_stale(h1), Some((&3, true)));
+        buffer.clear();
+        assert_eq!(buffer.get_allowing_stale(h1), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot push into a zero-capacity GenerationalBuffer")]
+    fn test_push_zero_capacity() {
+        // such a buffer can't be built with the public API
+        let mut buffer = GenerationalBuffer {
+            max_capacity: 0,
+            ..GenerationalBuffer::new(1)
+        };
+        buffer.push(1);
+    }
+
+    #[test]
+    fn test_iterator_bounds() {
+        fn len<I: DoubleEndedIterator + ExactSizeIterator + FusedIterator>(iter: I) -> usize {
+            iter.len()
+        }
+        let mut buffer = GenerationalBuffer::new(4);
+        let handles: Vec<_> = (0..6).map(|i| buffer.push(i)).collect();
+        assert_eq!(len(buffer.iter_chronological()), 4);
+        assert_eq!(len(buffer.enumerate_chronological()), 4);
+        assert_eq!(len(buffer.iter_after(handles[3])), 2);
+        assert_eq!(len(buffer.iter_rev()), 4);
+        assert_eq!(len(buffer.range_chronological(1..3)), 2);
+        assert_eq!(len(buffer.handles_rev()), 4);
+        let mut evens = buffer.handles_where(|v| v % 2 == 0);
+        assert_eq!(evens.next_back(), Some(handles[4]));
+        assert_eq!(evens.next(), Some(handles[2]));
+        assert_eq!(evens.next(), None);
+        assert_eq!(evens.next(), None);
+    }
+
+    #[test]
+    fn test_reversed_iterators() {
+        let mut buffer = GenerationalBuffer::new(3);
+        for i in 0..5 {
+            buffer.push(i);
+        }
+        // the order in memory is a permutation of the entries
+        let mut values: Vec<i32> = buffer.values_unordered().rev().copied().collect();
+        values.sort();
+        assert_eq!(values, vec![2, 3, 4]);
+        let handles: Vec<_> = buffer.handles().rev().collect();
+        let expected: Vec<_> = buffer.iter().rev().map(|(h, _)| h).collect();
+        assert_eq!(handles, expected);
+        let values: Vec<i32> = buffer.iter_rev().map(|(_, &v)| v).collect();
+        assert_eq!(values, vec![4, 3, 2]);
+    }
+
+    #[test]
+    fn test_modify() {
+        let mut buffer = GenerationalBuffer::new(2);
+        let h1 = buffer.push(vec![1]);
+        let h2 = buffer.push(vec![2]);
+        assert!(buffer.modify(h2, |mut v| {
+            v.push(3);
+            v
+        }));
+        assert_eq!(buffer.get(h2), Some(&vec![2, 3]));
+        buffer.push(vec![4]);
+        assert!(!buffer.modify(h1, |_| unreachable!()));
+        assert_eq!(buffer.len(), 2);
+    }
+
+    #[test]
+    fn test_modify_panic() {
+        let mut buffer = GenerationalBuffer::new(2);
+        let h1 = buffer.push("a".to_string());
+        let h2 = buffer.push("b".to_string());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            buffer.modify(h1, |_| panic!("failed transform"));
+        }));
+        assert!(result.is_err());
+        assert!(!buffer.is_valid(h1));
+        assert!(buffer.is_valid(h2));
+        assert_eq!(buffer.len(), 1);
+    }
+
+    #[test]
+    fn test_replace_oldest() {
+        let mut buffer = GenerationalBuffer::new(3);
+        assert_eq!(buffer.replace_oldest(0), None);
+        let handles: Vec<_> = (1..=4).map(|i| buffer.push(i)).collect();
+        assert_eq!(buffer.replace_oldest(20), Some((handles[1], 2)));
+        assert_eq!(buffer.get(handles[1]), Some(&20));
+        let values: Vec<i32> = buffer.iter_chronological().map(|(_, &v)| v).collect();
+        assert_eq!(values, vec![20, 3, 4]);
+        buffer.push(5);
+        assert!(!buffer.is_valid(handles[1]));
+    }
+
+    fn check_generation_wrap<G: Generation>() {
+        let mut buffer: GenerationalBuffer<usize, G> =
+            GenerationalBuffer::with_generation_width(2);
+        buffer.current_generation = G::MAX;
+        let h1 = buffer.push(1);
+        let h2 = buffer.push(2);
+        let h3 = buffer.push(3);
+        assert_eq!(h2.generation, G::MAX);
+        assert_eq!(h3.generation, G::ZERO);
+        assert!(!buffer.is_valid(h1));
+        assert!(buffer.is_valid(h2));
+        assert!(buffer.is_valid(h3));
+        assert_eq!(Handle::generation_distance(h2, h3), 1);
+        assert_eq!(Handle::generation_distance(h3, h1), -1);
+    }
+
+    #[test]
+    fn test_generation_widths() {
+        check_generation_wrap::<u16>();
+        check_generation_wrap::<u32>();
+        check_generation_wrap::<u64>();
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_generation_width_sizes() {
+        use std::mem::size_of;
+        assert_eq!(size_of::<Slot<u16>>(), 24);
+        assert_eq!(size_of::<Slot<u32>>(), 24);
+        assert_eq!(size_of::<Slot<u64>>(), 32);
+        // the index makes handles the same size
+        assert_eq!(size_of::<Handle<(), u16>>(), 16);
+        assert_eq!(size_of::<Handle<(), u32>>(), 16);
+        assert_eq!(size_of::<Handle<(), u64>>(), 16);
+    }
+
+    #[test]
+    fn test_u16_generations_wrap_sooner() {
+        let mut buffer: GenerationalBuffer<u32, u16> =
+            GenerationalBuffer::with_generation_width(1);
+        let h0 = buffer.push(0);
+        for i in 1..=u16::MAX as u32 {
+            buffer.push(i);
+        }
+        assert!(!buffer.is_valid(h0));
+        // after 2^16 laps, the stale handle can't be told apart
+        let h = buffer.push(65536);
+        assert_eq!(h, h0);
+        assert!(buffer.is_valid(h0));
+    }
+
+    #[test]
+    fn test_debug_assert_resolves() {
+        let mut buffer = GenerationalBuffer::new(2);
+        let h1 = buffer.push(1);
+        buffer.debug_assert_resolves(h1, &1);
+    }
+
+    #[test]
+    #[should_panic(expected = "resolves to 1 instead of 2")]
+    fn test_debug_assert_resolves_mismatch() {
+        let mut buffer = GenerationalBuffer::new(2);
+        let h1 = buffer.push(1);
+        buffer.debug_assert_resolves(h1, &2);
+    }
+
+    #[test]
+    #[should_panic(expected = "is stale (its slot now holds 3), expected 1")]
+    fn test_debug_assert_resolves_stale() {
+        let mut buffer = GenerationalBuffer::new(2);
+        let h1 = buffer.push(1);
+        buffer.push(2);
+        buffer.push(3);
+        buffer.debug_assert_resolves(h1, &1);
+    }
+
+    #[test]
+    fn test_logical_index() {
+        let mut buffer = GenerationalBuffer::new(3);
+        let handles: Vec<_> = (0..8).map(|i| buffer.push(i)).collect();
+        let indices: Vec<_> = handles.iter().map(|&h| buffer.logical_index(h)).collect();
+        assert_eq!(indices, vec![None, None, None, None, None, Some(5), Some(6), Some(7)]);
+        buffer.clear();
+        let h = buffer.push(8);
+        assert!(buffer.logical_index(h) > indices[7]);
+    }
+
+    #[test]
+    fn test_logical_index_after_growth() {
+        let mut buffer = GenerationalBuffer::new(3).grow_until(8);
+        let mut handles: Vec<_> = (0..5).map(|i| buffer.push(i)).collect();
+        assert_eq!(buffer.capacity(), 6);
+        buffer.take(handles[1]);
+        buffer.resize_with(7, || 5);
+        handles.extend((6..10).map(|i| buffer.push(i)));
+        assert_eq!(buffer.capacity(), 8);
+        let before = buffer.epoch();
+        // each value is the number of values pushed before it, including
+        // the ones pushed before the growth
+        for &handle in &handles {
+            let logical = buffer.logical_index(handle);
+            assert_eq!(logical, buffer.get(handle).map(|&v| v as u64));
+            if let Some(logical) = logical {
+                assert_eq!(buffer.handle_at_logical(logical), Some(handle));
+                assert!(buffer.slot_changed_between(handle, Epoch(logical), before));
+            }
+        }
+        assert_eq!(buffer.handle_at_logical(1), None);
+        assert_eq!(buffer.handle_at_logical(5).and_then(|h| buffer.get(h)), Some(&5));
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let mut buffer = GenerationalBuffer::new(3);
+        let h1 = buffer.push(1);
+        let h2 = buffer.push(2);
+        let h3 = buffer.push(3);
+        assert!(buffer.invalidate(h2));
+        assert!(!buffer.invalidate(h2));
+        assert_eq!(buffer.get(h2), None);
+        assert_eq!(buffer.get(h1), Some(&1));
+        assert_eq!(buffer.get(h3), Some(&3));
+        assert_eq!(buffer.len(), 2);
+    }
+
+    #[test]
+    fn test_range_chronological() {
+        let mut buffer = GenerationalBuffer::new(5);
+        for i in 0..8 {
+            buffer.push(i);
+        }
+        // physical order is [5, 6, 7, 3, 4]
+        let window: Vec<i32> = buffer.range_chronological(1..4).map(|(_, &v)| v).collect();
+        assert_eq!(window, vec![4, 5, 6]);
+        let window: Vec<i32> = buffer.range_chronological(3..10).map(|(_, &v)| v).collect();
+        assert_eq!(window, vec![6, 7]);
+        assert_eq!(buffer.range_chronological(5..9).count(), 0);
+        for (handle, value) in buffer.range_chronological(0..5) {
+            assert_eq!(buffer.get(handle), Some(value));
+        }
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary() {
+        use arbitrary::{
+            Arbitrary,
+            Unstructured,
+        };
+        let bytes: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let mut u = Unstructured::new(&bytes);
+        let buffer: GenerationalBuffer<u8> = GenerationalBuffer::arbitrary(&mut u).unwrap();
+        assert!(!buffer.is_empty());
+        assert!(buffer.len() <= buffer.capacity());
+        assert_eq!(buffer.handles().len(), buffer.len());
+        for handle in buffer.handles() {
+            assert!(buffer.is_valid(handle));
+        }
+        let handle: Handle<u8> = Handle::arbitrary(&mut u).unwrap();
+        let _ = buffer.get(handle);
+    }
+
+    #[test]
+    fn test_check_invariants() {
+        let mut buffer = GenerationalBuffer::new(4);
+        assert_eq!(buffer.check_invariants(), Ok(()));
+        let handles: Vec<_> = (0..11).map(|i| buffer.push(i)).collect();
+        assert_eq!(buffer.check_invariants(), Ok(()));
+        buffer.invalidate(handles[9]);
+        buffer.replace_oldest(100);
+        buffer.dedup_by_key(|v| v % 2);
+        assert_eq!(buffer.check_invariants(), Ok(()));
+        buffer.extract_if(|&v| v > 50).for_each(drop);
+        buffer.push(12);
+        assert_eq!(buffer.check_invariants(), Ok(()));
+        buffer.clear();
+        assert_eq!(buffer.check_invariants(), Ok(()));
+
+        buffer.len = 3;
+        assert!(buffer.check_invariants().is_err());
+    }
+
+    #[test]
+    fn test_take() {
+        let mut buffer = GenerationalBuffer::new(3);
+        let h1 = buffer.push("a".to_string());
+        let h2 = buffer.push("b".to_string());
+        let h3 = buffer.push("c".to_string());
+        assert_eq!(buffer.take(h2), Some("b".to_string()));
+        assert_eq!(buffer.take(h2), None);
+        assert_eq!(buffer.get(h2), None);
+        assert_eq!(buffer.len(), 2);
+        buffer.debug_assert_resolves(h1, &"a".to_string());
+        buffer.debug_assert_resolves(h3, &"c".to_string());
+        assert_eq!(buffer.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_allocated_capacity() {
+        let mut buffer = GenerationalBuffer::new(100);
+        assert_eq!(buffer.allocated_capacity(), 0);
+        buffer.push(1);
+        assert!(buffer.allocated_capacity() < buffer.capacity());
+        for i in 0..100 {
+            buffer.push(i);
+        }
+        assert!(buffer.allocated_capacity() >= buffer.capacity());
+        assert_eq!(buffer.capacity(), 100);
+    }
+
+    #[test]
+    fn test_fill_with() {
+        let mut buffer = GenerationalBuffer::new(5);
+        let h1 = buffer.push(1);
+        let h2 = buffer.push(2);
+        let mut next = 10;
+        let handles = buffer.fill_with(|| {
+            next += 1;
+            next
+        });
+        assert_eq!(handles.len(), 3);
+        assert!(buffer.is_full());
+        for (handle, expected) in handles.into_iter().zip(11..) {
+            buffer.debug_assert_resolves(handle, &expected);
+        }
+        assert!(buffer.is_valid(h1));
+        assert!(buffer.is_valid(h2));
+        assert!(buffer.fill_with(|| 0).is_empty());
+    }
+
+    #[test]
+    fn test_grow_until() {
+        let mut buffer = GenerationalBuffer::new(2).grow_until(10);
+        let handles: Vec<_> = (0..10).map(|i| buffer.push(i)).collect();
+        assert_eq!(buffer.capacity(), 10);
+        assert_eq!(buffer.len(), 10);
+        for (i, &handle) in handles.iter().enumerate() {
+            buffer.debug_assert_resolves(handle, &(i as i32));
+        }
+        assert_eq!(buffer.check_invariants(), Ok(()));
+
+        // the ceiling is reached, so entries are now evicted
+        buffer.push(10);
+        assert_eq!(buffer.capacity(), 10);
+        assert_eq!(buffer.len(), 10);
+        assert!(!buffer.is_valid(handles[0]));
+        assert!(buffer.is_valid(handles[1]));
+        let values: Vec<i32> = buffer.iter_chronological().map(|(_, &v)| v).collect();
+        assert_eq!(values, (1..=10).collect::<Vec<_>>());
+        assert_eq!(buffer.check_invariants(), Ok(()));
+
+        // filling doesn't grow the buffer
+        let mut buffer = GenerationalBuffer::new(2).grow_until(10);
+        buffer.push(0);
+        assert_eq!(buffer.fill_with(|| 1).len(), 1);
+        assert_eq!(buffer.capacity(), 2);
+        buffer.push(2);
+        assert_eq!(buffer.fill_with(|| 3).len(), 1);
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![0, 1, 2, 3]);
+        // nor does it after a shrink, which lowers the ceiling
+        buffer.shrink_capacity_keeping_newest(3);
+        buffer.push(4);
+        assert_eq!(buffer.capacity(), 3);
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![2, 3, 4]);
+        assert_eq!(buffer.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_grow_after_wrap() {
+        let mut buffer = GenerationalBuffer::new(3);
+        for i in 0..4 {
+            buffer.push(i);
+        }
+        let mut buffer = buffer.grow_until(6);
+        let handles: Vec<_> = (4..8).map(|i| buffer.push(i)).collect();
+        assert_eq!(buffer.capacity(), 6);
+        let values: Vec<i32> = buffer.iter_chronological().map(|(_, &v)| v).collect();
+        // the buffer grew as soon as it was full
+        assert_eq!(values, vec![2, 3, 4, 5, 6, 7]);
+        for (&handle, i) in handles.iter().zip(4..) {
+            buffer.debug_assert_resolves(handle, &i);
+        }
+        assert_eq!(buffer.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_sorted_by() {
+        let mut buffer = GenerationalBuffer::new(4);
+        for i in [5, 1, 9, 3, 7] {
+            buffer.push(i);
+        }
+        let sorted = buffer.sorted_by(|&v| std::cmp::Reverse(v));
+        let values: Vec<i32> = sorted.iter().map(|(_, &v)| v).collect();
+        assert_eq!(values, vec![9, 7, 3, 1]);
+        for (handle, value) in sorted {
+            assert_eq!(buffer.get(handle), Some(value));
+        }
+    }
+
+    // Also meant to be checked with `cargo miri test`
+    #[test]
+    fn test_push_deferring_drop() {
+        use std::{
+            cell::Cell,
+            rc::Rc,
+        };
+        struct Droppy(Rc<Cell<usize>>);
+        impl Drop for Droppy {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+        let drops = Rc::new(Cell::new(0));
+        let mut buffer = GenerationalBuffer::new(2);
+        let mut deferred = Vec::new();
+        for _ in 0..5 {
+            let (_, evicted) = buffer.push_deferring_drop(Droppy(Rc::clone(&drops)));
+            deferred.extend(evicted);
+        }
+        assert_eq!(deferred.len(), 3);
+        assert_eq!(drops.get(), 0);
+        drop(deferred);
+        assert_eq!(drops.get(), 3);
+        buffer.push(Droppy(Rc::clone(&drops)));
+        assert_eq!(drops.get(), 4);
+        drop(buffer);
+        assert_eq!(drops.get(), 6);
+    }
+
+    #[test]
+    fn test_newest_and_oldest_mut() {
+        let mut buffer = GenerationalBuffer::new(3);
+        assert!(buffer.newest_mut().is_none());
+        assert!(buffer.oldest_mut().is_none());
+        let h1 = buffer.push(1);
+        let h2 = buffer.push(2);
+        // partially filled
+        let (handle, value) = buffer.newest_mut().unwrap();
+        assert_eq!(handle, h2);
+        *value = 20;
+        let (handle, value) = buffer.oldest_mut().unwrap();
+        assert_eq!(handle, h1);
+        *value = 10;
+        assert_eq!(buffer.get(h1), Some(&10));
+        assert_eq!(buffer.get(h2), Some(&20));
+        // wrapped
+        buffer.push(3);
+        let h4 = buffer.push(4);
+        *buffer.newest_mut().unwrap().1 += 40;
+        *buffer.oldest_mut().unwrap().1 += 200;
+        assert_eq!(buffer.get(h4), Some(&44));
+        assert_eq!(buffer.get(h2), Some(&220));
+        assert_eq!(buffer.newest(), Some((h4, &44)));
+        assert_eq!(buffer.oldest(), Some((h2, &220)));
+    }
+
+    #[test]
+    fn test_copy_chronological_into() {
+        let mut buffer = GenerationalBuffer::new(3);
+        for i in 1..=4 {
+            buffer.push(i);
+        }
+        let mut short = [0; 2];
+        assert_eq!(buffer.copy_chronological_into(&mut short), 2);
+        assert_eq!(short, [2, 3]);
+        let mut exact = [0; 3];
+        assert_eq!(buffer.copy_chronological_into(&mut exact), 3);
+        assert_eq!(exact, [2, 3, 4]);
+        let mut long = [0; 5];
+        assert_eq!(buffer.copy_chronological_into(&mut long), 3);
+        assert_eq!(long, [2, 3, 4, 0, 0]);
+    }
+
+    #[test]
+    fn test_make_contiguous_in() {
+        let mut buffer = GenerationalBuffer::new(3);
+        let mut dst = vec![7; 5];
+        buffer.push(1);
+        buffer.push(2);
+        let values = buffer.make_contiguous_in(&mut dst);
+        assert_eq!(values.len(), buffer.len());
+        let ptr = values.as_ptr();
+        // reading through the pointer, as C would
+        unsafe {
+            assert_eq!(*ptr, 1);
+            assert_eq!(*ptr.add(1), 2);
+        }
+        buffer.push(3);
+        buffer.push(4);
+        assert_eq!(buffer.make_contiguous_in(&mut dst), &[2, 3, 4]);
+    }
+
+    #[test]
+    fn test_as_contiguous_slice() {
+        let mut buffer = GenerationalBuffer::new(4);
+        assert_eq!(buffer.as_contiguous_slice(), Some(&[][..]));
+        let handles: Vec<_> = (0..3).map(|i| buffer.push(i)).collect();
+        assert_eq!(buffer.as_contiguous_slice(), Some(&[0, 1, 2][..]));
+        assert_eq!(buffer.get_slice_if_contiguous(1..9), Some(&[1, 2][..]));
+        // the storage wraps once the oldest entries are evicted
+        for i in 3..6 {
+            buffer.push(i);
+        }
+        assert_eq!(buffer.as_contiguous_slice(), None);
+        assert_eq!(buffer.get_slice_if_contiguous(0..2), Some(&[2, 3][..]));
+        assert_eq!(buffer.get_slice_if_contiguous(1..3), None);
+        assert_eq!(buffer.make_contiguous(), &[2, 3, 4, 5]);
+        assert_eq!(buffer.as_contiguous_slice(), Some(&[2, 3, 4, 5][..]));
+        buffer.debug_assert_resolves(handles[2], &2);
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_upsert() {
+        let mut buffer = GenerationalBuffer::new(2);
+        let h1 = buffer.push(1);
+        assert_eq!(buffer.upsert(h1, 10), h1);
+        assert_eq!(buffer.get(h1), Some(&10));
+        assert_eq!(buffer.len(), 1);
+        buffer.push(2);
+        buffer.push(3);
+        let h4 = buffer.upsert(h1, 4);
+        assert_ne!(h4, h1);
+        assert_eq!(buffer.get(h4), Some(&4));
+        assert!(!buffer.is_valid(h1));
+    }
+
+    #[test]
+    fn test_const_accessors() {
+        const fn free_slots(buffer: &GenerationalBuffer<u8>) -> usize {
+            if buffer.is_full() {
+                0
+            } else {
+                buffer.max_capacity() - buffer.len()
+            }
+        }
+        let mut buffer = GenerationalBuffer::new(3);
+        assert_eq!(free_slots(&buffer), 3);
+        assert!(buffer.is_empty());
+        buffer.push(1);
+        assert_eq!(free_slots(&buffer), 2);
+        assert_eq!(buffer.capacity(), buffer.max_capacity());
+
+        // evaluated at compile time, on a buffer built without new(), which isn't const
+        const EMPTY: &GenerationalBuffer<u8> = &GenerationalBuffer {
+            values: VecDeque::new(),
+            owners: VecDeque::new(),
+            slots: Vec::new(),
+            free: VecDeque::new(),
+            front: 0,
+            max_capacity: 4,
+            capacity_ceiling: 4,
+            len: 0,
+            current_generation: 0,
+            generation_overflow: GenerationOverflow::Wrap,
+            total_pushed: 0,
+            total_evicted: 0,
+            on_evict: None,
+        };
+        const FREE: usize = free_slots(EMPTY);
+        const _: () = assert!(FREE == 4 && EMPTY.capacity() == EMPTY.max_capacity());
+        const _: () = assert!(EMPTY.is_empty() && !EMPTY.is_full());
+        const CAP: usize = GenerationalBuffer::<u64>::capacity_for_bytes(4096);
+        const _: () = assert!(CAP > 0 && CAP <= 4096 / 8);
+        assert_eq!(FREE, 4);
+    }
+
+    #[test]
+    fn test_drain_oldest() {
+        let mut buffer = GenerationalBuffer::new(4);
+        let handles: Vec<_> = (0..6).map(|i| buffer.push(i)).collect();
+        assert_eq!(buffer.drain_oldest(2), vec![2, 3]);
+        assert_eq!(buffer.len(), 2);
+        assert!(!buffer.is_valid(handles[2]));
+        assert!(!buffer.is_valid(handles[3]));
+        buffer.debug_assert_resolves(handles[4], &4);
+        buffer.debug_assert_resolves(handles[5], &5);
+        assert_eq!(buffer.drain_oldest(10), vec![4, 5]);
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_accessors_by_reference() {
+        struct Tracked {
+            handle: Handle<String>,
+        }
+        impl Tracked {
+            fn handle(&self) -> &Handle<String> {
+                &self.handle
+            }
+        }
+        let mut buffer = GenerationalBuffer::new(2);
+        let tracked = Tracked {
+            handle: buffer.push("a".to_string()),
+        };
+        assert!(buffer.is_valid(tracked.handle()));
+        assert_eq!(buffer.get(tracked.handle()).map(String::as_str), Some("a"));
+        buffer.get_mut(tracked.handle()).unwrap().push('b');
+        assert_eq!(buffer.get(tracked.handle()).map(String::as_str), Some("ab"));
+        let handles: Vec<_> = buffer.handles().collect();
+        for handle in &handles {
+            assert!(buffer.is_valid(handle));
+        }
+        buffer.push("c".to_string());
+        buffer.push("d".to_string());
+        assert!(!buffer.is_valid(tracked.handle()));
+        assert_eq!(buffer.get(tracked.handle()), None);
+        assert_eq!(buffer.get_mut(tracked.handle()), None);
+    }
+
+    #[test]
+    fn test_debug_chronological() {
+        let mut buffer = GenerationalBuffer::new(3);
+        for i in 1..=5 {
+            buffer.push(i);
+        }
+        assert_eq!(format!("{:?}", buffer.debug_chronological()), "{2: 3, 1: 4, 0: 5}");
+        buffer.clear();
+        assert_eq!(format!("{:?}", buffer.debug_chronological()), "{}");
+    }
+
+    #[test]
+    fn test_get_mut_two() {
+        let mut buffer = GenerationalBuffer::new(3);
+        let h1 = buffer.push(1);
+        let h2 = buffer.push(2);
+        let h3 = buffer.push(3);
+        let (a, b) = buffer.get_mut_two(h3, h2).unwrap();
+        std::mem::swap(a, b);
+        assert_eq!(buffer.get(h2), Some(&3));
+        assert_eq!(buffer.get(h3), Some(&2));
+        assert_eq!(buffer.get_mut_two(h1, h1), Err(TwoMutError::SameSlot));
+        buffer.push(4);
+        assert_eq!(buffer.get_mut_two(h1, h2), Err(TwoMutError::FirstInvalid));
+        assert_eq!(buffer.get_mut_two(h2, h1), Err(TwoMutError::SecondInvalid));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_choose() {
+        use rand::{
+            SeedableRng,
+            rngs::StdRng,
+        };
+        let mut buffer = GenerationalBuffer::new(4);
+        assert!(buffer.choose(&mut StdRng::seed_from_u64(42)).is_none());
+        for i in 0..6 {
+            buffer.push(i);
+        }
+        let a = buffer.choose(&mut StdRng::seed_from_u64(42)).unwrap();
+        let b = buffer.choose(&mut StdRng::seed_from_u64(42)).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(buffer.get(a.0), Some(a.1));
+        buffer.drain_oldest(1);
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let (handle, value) = buffer.choose(&mut rng).unwrap();
+            assert_eq!(buffer.get(handle), Some(value));
+        }
+    }
+
+    #[test]
+    fn test_recalibrate_generations() {
+        let mut buffer: GenerationalBuffer<i32> = GenerationalBuffer::new(3);
+        let mut handles = Vec::new();
+        for i in 0..1000 {
+            handles.push(buffer.push(i));
+        }
+        buffer.invalidate(handles[998]);
+        let old_997 = handles[997];
+        let remapping = buffer.recalibrate_generations();
+        buffer.check_invariants().unwrap();
+        assert_eq!(buffer.current_generation, 1);
+        assert_eq!(remapping.len(), 2);
+        assert_eq!(remapping[0].0, old_997);
+        for &(_, new) in &remapping {
+            assert!(buffer.is_valid(new));
+        }
+        assert_eq!(buffer.get(remapping[0].1), Some(&997));
+        assert_eq!(buffer.get(remapping[1].1), Some(&999));
+        // the buffer keeps working normally
+        let h = buffer.push(1000);
+        assert_eq!(buffer.get(h), Some(&1000));
+        assert_eq!(buffer.get(remapping[0].1), Some(&997));
+        buffer.push(1001);
+        assert_eq!(buffer.get(remapping[0].1), None);
+        assert_eq!(buffer.get(remapping[1].1), Some(&999));
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_clear_avoids_aba() {
+        let mut buffer = GenerationalBuffer::new(4);
+        let old = buffer.push("before");
+        buffer.clear();
+        let new = buffer.push("after");
+        assert_eq!(old.index, new.index);
+        assert_ne!(old, new);
+        assert_eq!(buffer.get(old), None);
+        assert_eq!(buffer.get(new), Some(&"after"));
+        // also when the ring was partially used
+        buffer.push("more");
+        buffer.clear();
+        let newer = buffer.push("again");
+        assert_ne!(new, newer);
+        assert!(!buffer.is_valid(new));
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_generation_overflow_wrap() {
+        let mut buffer = GenerationalBuffer::new(2);
+        buffer.current_generation = u32::MAX;
+        let h1 = buffer.push(1);
+        buffer.push(2);
+        let h3 = buffer.push(3);
+        assert_eq!(h3.generation, 0);
+        assert!(!buffer.is_valid(h1));
+        assert!(!buffer.is_generation_saturated());
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_generation_overflow_saturate() {
+        let mut buffer =
+            GenerationalBuffer::new(2).on_generation_overflow(GenerationOverflow::Saturate);
+        buffer.current_generation = u32::MAX - 1;
+        buffer.push(0);
+        assert!(!buffer.is_generation_saturated());
+        buffer.push(1);
+        let h1 = buffer.push(1);
+        buffer.push(2);
+        assert!(buffer.is_generation_saturated());
+        let h3 = buffer.push(3);
+        assert_eq!(h3.generation, u32::MAX);
+        assert_eq!(buffer.current_generation, u32::MAX);
+        // the stale handle can't be told apart from the new one
+        assert_eq!(buffer.get(h1), Some(&3));
+        buffer.check_invariants().unwrap();
+        buffer.clear();
+        assert_eq!(buffer.current_generation, u32::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "generation overflow")]
+    fn test_generation_overflow_panic() {
+        let mut buffer =
+            GenerationalBuffer::new(2).on_generation_overflow(GenerationOverflow::Panic);
+        buffer.current_generation = u32::MAX;
+        buffer.push(1);
+        buffer.push(2);
+        buffer.push(3); // starts a new lap of the ring
+    }
+
+    #[test]
+    fn test_validity_bitset() {
+        let mut buffer = GenerationalBuffer::new(50);
+        let handles: Vec<_> = (0..150).map(|i| buffer.push(i)).collect();
+        buffer.invalidate(handles[120]);
+        let bitset = buffer.validity_bitset(&handles);
+        assert_eq!(bitset.len(), 3);
+        for (i, &handle) in handles.iter().enumerate() {
+            let bit = bitset[i / 64] >> (i % 64) & 1 == 1;
+            assert_eq!(bit, buffer.is_valid(handle), "handle {i}");
+        }
+        assert_eq!(bitset[2] >> (150 - 128), 0);
+        assert!(buffer.validity_bitset(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_push_many_tracking() {
+        let mut buffer = GenerationalBuffer::new(3);
+        let (handles, evicted) = buffer.push_many_tracking([1, 2, 3]);
+        assert_eq!(handles.len(), 3);
+        assert!(evicted.is_empty());
+        let (new_handles, evicted) = buffer.push_many_tracking(4..10);
+        assert_eq!(evicted, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(new_handles.len(), 6);
+        assert!(handles.iter().all(|&h| !buffer.is_valid(h)));
+        let values: Vec<_> = new_handles.iter().filter_map(|&h| buffer.get(h)).collect();
+        assert_eq!(values, vec![&7, &8, &9]);
+    }
+
+    #[test]
+    fn test_compact_remapping() {
+        let mut buffer = GenerationalBuffer::new(5);
+        let handles: Vec<_> = (0..7).map(|i| buffer.push(i)).collect();
+        buffer.invalidate(handles[3]);
+        buffer.invalidate(handles[5]);
+        let remapping = buffer.compact_remapping();
+        buffer.check_invariants().unwrap();
+        assert_eq!(remapping.len(), 3);
+        for (i, handle) in handles.iter().enumerate() {
+            match remapping.get(handle) {
+                Some(&new) => assert_eq!(buffer.get(new), Some(&i)),
+                None => assert!([0, 1, 3, 5].contains(&i)),
+            }
+            assert!(!buffer.is_valid(handle));
+        }
+        let values: Vec<_> = buffer.iter_chronological().map(|(_, &v)| v).collect();
+        assert_eq!(values, vec![2, 4, 6]);
+        // the freed slots are used before evicting
+        buffer.push(7);
+        buffer.push(8);
+        assert_eq!(buffer.len(), 5);
+        assert!(buffer.is_valid(remapping[&handles[2]]));
+        buffer.push(9);
+        assert!(!buffer.is_valid(remapping[&handles[2]]));
+        buffer.check_invariants().unwrap();
+        // a full buffer is compacted too
+        let remapping = buffer.compact_remapping();
+        assert_eq!(remapping.len(), 5);
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_capacity_for_bytes() {
+        let slot_size = std::mem::size_of::<Slot<u32>>() + std::mem::size_of::<usize>();
+        assert_eq!(
+            GenerationalBuffer::<u64>::capacity_for_bytes(100 * (8 + slot_size) + 1),
+            100
+        );
+        assert_eq!(GenerationalBuffer::<u64>::capacity_for_bytes(0), 1);
+        // zero-sized values still need their slot
+        let capacity = GenerationalBuffer::<()>::capacity_for_bytes(1024);
+        assert_eq!(capacity, 1024 / slot_size);
+        assert!(capacity <= 1024 / 4);
+        assert!(
+            GenerationalBuffer::<(), u16>::capacity_for_bytes(1024)
+                >= GenerationalBuffer::<(), u64>::capacity_for_bytes(1024)
+        );
+    }
+
+    #[test]
+    fn test_iter_after() {
+        let mut buffer = GenerationalBuffer::new(5);
+        let handles: Vec<_> = (0..8).map(|i| buffer.push(i)).collect();
+        buffer.invalidate(handles[6]);
+        // first page, from the start
+        let page: Vec<_> = buffer.iter_chronological().take(2).collect();
+        assert_eq!(page.iter().map(|&(_, &v)| v).collect::<Vec<_>>(), vec![3, 4]);
+        // second page, across the wrap
+        let last = page[1].0;
+        let page: Vec<_> = buffer.iter_after(last).map(|(_, &v)| v).collect();
+        assert_eq!(page, vec![5, 7]);
+        let page: Vec<_> = buffer.iter_after(handles[5]).map(|(_, &v)| v).collect();
+        assert_eq!(page, vec![7]);
+        assert_eq!(buffer.iter_after(handles[7]).count(), 0);
+        // stale handles restart from the oldest entry
+        let page: Vec<_> = buffer.iter_after(handles[0]).map(|(_, &v)| v).collect();
+        assert_eq!(page, vec![3, 4, 5, 7]);
+    }
+
+    #[test]
+    fn test_extend_with_evictions() {
+        let mut buffer = GenerationalBuffer::new(10);
+        let mut evicted = Vec::new();
+        buffer.extend_with_evictions(0..1000, |value| evicted.push(value));
+        assert_eq!(evicted.len(), 990);
+        assert_eq!(evicted[..3], [0, 1, 2]);
+        assert_eq!(evicted.last(), Some(&989));
+        assert_eq!(buffer.len(), 10);
+        assert_eq!(buffer.newest().map(|(_, &v)| v), Some(999));
+    }
+
+    #[test]
+    fn test_is_valid_matches_inferred_generations() {
+        // without removal, the slot and the generation of a value can be
+        // inferred from the number of values pushed before it, as each lap
+        // of the ring advances the generation
+        let mut buffer = GenerationalBuffer::new(7);
+        let mut handles = Vec::new();
+        for i in 0..100 {
+            handles.push(buffer.push(i));
+            for (j, &handle) in handles.iter().enumerate() {
+                assert_eq!(handle.index, j % 7);
+                assert_eq!(handle.generation, (j / 7) as u32);
+                assert_eq!(buffer.is_valid(handle), j + 7 > i);
+            }
+        }
+    }
+
+    #[test]
+    fn test_handle_at_logical() {
+        let mut buffer = GenerationalBuffer::new(3);
+        let handles: Vec<_> = (0..8).map(|i| buffer.push(i)).collect();
+        for &handle in &handles[5..] {
+            let logical = buffer.logical_index(handle).unwrap();
+            assert_eq!(buffer.handle_at_logical(logical), Some(handle));
+        }
+        for logical in 0..5 {
+            assert_eq!(buffer.handle_at_logical(logical), None);
+        }
+        assert_eq!(buffer.handle_at_logical(8), None);
+        buffer.invalidate(handles[6]);
+        assert_eq!(buffer.handle_at_logical(6), None);
+    }
+
+    #[test]
+    fn test_project_to_vec() {
+        struct Point {
+            x: i32,
+            _y: i32,
+        }
+        let mut buffer = GenerationalBuffer::new(3);
+        for x in 0..5 {
+            buffer.push(Point { x, _y: -x });
+        }
+        assert_eq!(buffer.project_to_vec(|p| p.x), vec![2, 3, 4]);
+        assert!(GenerationalBuffer::<Point>::new(2).project_to_vec(|p| p.x).is_empty());
+    }
+
+    #[test]
+    fn test_push_front() {
+        fn values(buffer: &GenerationalBuffer<i32>) -> Vec<i32> {
+            buffer.iter_chronological().map(|(_, &v)| v).collect()
+        }
+        let mut buffer = GenerationalBuffer::new(4);
+        let h1 = buffer.push(1);
+        let h0 = buffer.push_front(0);
+        buffer.check_invariants().unwrap();
+        assert_eq!(values(&buffer), vec![0, 1]);
+        buffer.push(2);
+        let hm1 = buffer.push_front(-1);
+        assert_eq!(values(&buffer), vec![-1, 0, 1, 2]);
+        buffer.check_invariants().unwrap();
+        // the buffer is full: push_front evicts the newest entry
+        let hm2 = buffer.push_front(-2);
+        assert_eq!(values(&buffer), vec![-2, -1, 0, 1]);
+        buffer.check_invariants().unwrap();
+        assert_eq!(buffer.get(h0), Some(&0));
+        assert_eq!(buffer.get(h1), Some(&1));
+        assert_eq!(buffer.get(hm1), Some(&-1));
+        // and push evicts the oldest entry, which was pushed at the front
+        let h3 = buffer.push(3);
+        assert_eq!(values(&buffer), vec![-1, 0, 1, 3]);
+        assert!(!buffer.is_valid(hm2));
+        assert!(buffer.is_valid(h3));
+        buffer.check_invariants().unwrap();
+        for i in 4..10 {
+            buffer.push(i);
+            buffer.check_invariants().unwrap();
+        }
+        assert_eq!(values(&buffer), vec![6, 7, 8, 9]);
+        assert!(![h0, h1, hm1, hm2, h3].iter().any(|h| buffer.is_valid(h)));
+    }
+
+    #[test]
+    fn test_push_front_into_vacant_slot() {
+        let mut buffer = GenerationalBuffer::new(3);
+        let handles: Vec<_> = (0..3).map(|i| buffer.push(i)).collect();
+        buffer.invalidate(handles[0]);
+        let h = buffer.push_front(10);
+        assert_eq!(h.index, handles[0].index);
+        assert!(!buffer.is_valid(handles[0]));
+        assert_eq!(buffer.get(h), Some(&10));
+        assert_eq!(buffer.len(), 3);
+        let values = buffer.project_to_vec(|&v| v);
+        assert_eq!(values, vec![10, 1, 2]);
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_push_front_allocation_and_generation() {
+        // the slots are allocated one at a time, as with push
+        let mut buffer = GenerationalBuffer::new(usize::MAX);
+        buffer.push_front(1);
+        let h = buffer.push_front(0);
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![0, 1]);
+        assert!(buffer.allocated_capacity() < 1000);
+        assert_eq!(buffer.current_generation, 0);
+        assert_eq!(buffer.logical_index(h), Some(1));
+        buffer.check_invariants().unwrap();
+        // reusing the slot of the evicted entry advances the generation once
+        let mut buffer = GenerationalBuffer::new(2);
+        let handles = [buffer.push(0), buffer.push(1)];
+        let h = buffer.push_front(-1);
+        assert_eq!(h.index, handles[1].index);
+        assert_eq!(buffer.current_generation, 1);
+        assert!(!buffer.is_valid(handles[1]));
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_coalesce_newest() {
+        // consecutive events of the same kind are counted in one entry
+        let mut buffer = GenerationalBuffer::new(3);
+        let record = |buffer: &mut GenerationalBuffer<(char, usize)>, kind| {
+            buffer.coalesce_newest(
+                || (kind, 1),
+                |(k, count)| {
+                    let absorbed = *k == kind;
+                    if absorbed {
+                        *count += 1;
+                    }
+                    absorbed
+                },
+            )
+        };
+        let h1 = record(&mut buffer, 'a');
+        assert_eq!(buffer.get(h1), Some(&('a', 1)));
+        assert_eq!(record(&mut buffer, 'a'), h1);
+        assert_eq!(record(&mut buffer, 'a'), h1);
+        let h2 = record(&mut buffer, 'b');
+        assert_ne!(h1, h2);
+        assert_eq!(buffer.project_to_vec(|&e| e), vec![('a', 3), ('b', 1)]);
+    }
+
+    #[test]
+    fn test_total_evicted() {
+        let mut buffer = GenerationalBuffer::new(7);
+        for i in 0..100 {
+            buffer.push(i);
+        }
+        buffer.push_front(-1);
+        buffer.push_many_tracking(100..110);
+        assert_eq!(buffer.total_pushed(), 111);
+        assert_eq!(buffer.total_evicted(), buffer.total_pushed() - buffer.len() as u64);
+        buffer.clear();
+        assert_eq!(buffer.total_evicted(), 104);
+        buffer.push(0);
+        assert_eq!(buffer.total_pushed(), 112);
+    }
+
+    #[test]
+    fn test_handles_rev() {
+        let mut buffer = GenerationalBuffer::new(4);
+        assert_eq!(buffer.handles_rev().next(), None);
+        let handles: Vec<_> = (0..6).map(|i| buffer.push(i)).collect();
+        assert_eq!(buffer.handles_rev().next(), buffer.newest().map(|(h, _)| h));
+        let rev: Vec<_> = buffer.handles_rev().collect();
+        assert_eq!(rev, vec![handles[5], handles[4], handles[3], handles[2]]);
+    }
+
+    #[test]
+    fn test_set_capacity_to_len() {
+        let mut buffer = GenerationalBuffer::new(10);
+        let handles: Vec<_> = (0..3).map(|i| buffer.push(i)).collect();
+        buffer.set_capacity_to_len();
+        buffer.check_invariants().unwrap();
+        assert_eq!(buffer.capacity(), 3);
+        assert!(buffer.is_full());
+        assert!(handles.iter().all(|h| buffer.is_valid(h)));
+        let (_, evicted) = buffer.push_tracking(3);
+        assert_eq!(evicted, Some(handles[0]));
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![1, 2, 3]);
+        buffer.check_invariants().unwrap();
+        // on a full buffer, it's a no-op
+        buffer.set_capacity_to_len();
+        assert_eq!(buffer.capacity(), 3);
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![1, 2, 3]);
+        // the vacant slots of a wrapped buffer aren't counted
+        let mut buffer = GenerationalBuffer::new(10);
+        let handles: Vec<_> = (0..12).map(|i| buffer.push(i)).collect();
+        buffer.take(handles[7]);
+        buffer.set_capacity_to_len();
+        buffer.check_invariants().unwrap();
+        assert_eq!(buffer.capacity(), 9);
+        assert_eq!(buffer.max_capacity(), buffer.len());
+        for (&handle, i) in handles.iter().zip(0..).skip(2).filter(|&(_, i)| i != 7) {
+            buffer.debug_assert_resolves(handle, &i);
+        }
+        let (_, evicted) = buffer.push_tracking(12);
+        assert_eq!(evicted, Some(handles[2]));
+        assert_eq!(buffer.len(), 9);
+        buffer.check_invariants().unwrap();
+        // an empty buffer gets a capacity of 1
+        let mut buffer = GenerationalBuffer::new(10);
+        let h = buffer.push(0);
+        buffer.invalidate(h);
+        buffer.set_capacity_to_len();
+        assert_eq!(buffer.capacity(), 1);
+        let h2 = buffer.push(1);
+        assert_ne!(h, h2);
+        assert!(!buffer.is_valid(h));
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_map_in_place() {
+        let mut buffer = GenerationalBuffer::new(4);
+        let handles: Vec<_> = (0..6).map(|i| buffer.push(i as f64)).collect();
+        buffer.invalidate(handles[4]);
+        buffer.map_in_place(|v| *v *= 0.5);
+        assert_eq!(buffer.get(handles[2]), Some(&1.0));
+        assert_eq!(buffer.get(handles[3]), Some(&1.5));
+        assert_eq!(buffer.get(handles[5]), Some(&2.5));
+        assert_eq!(buffer.len(), 3);
+    }
+
+    #[test]
+    fn test_validate_handle_shape() {
+        let mut buffer = GenerationalBuffer::new(3);
+        let h = buffer.push(1);
+        assert!(buffer.validate_handle_shape(h));
+        assert!(buffer.validate_handle_shape(Handle::new(2, 7)));
+        let beyond = Handle::new(3, 0);
+        assert!(!buffer.validate_handle_shape(beyond));
+        assert!(!buffer.is_valid(beyond));
+    }
+
+    #[test]
+    fn test_layout_string() {
+        let mut buffer = GenerationalBuffer::new(4);
+        assert_eq!(buffer.layout_string(), "[|. . . .]");
+        let handles: Vec<_> = (0..2).map(|i| buffer.push(i)).collect();
+        assert_eq!(buffer.layout_string(), "[0@0 1@0|. .]");
+        for i in 2..6 {
+            buffer.push(i);
+        }
+        buffer.invalidate(handles[1]);
+        assert_eq!(buffer.layout_string(), "[4@1 5@1|2@0 3@0]");
+        buffer.take(buffer.newest().unwrap().0);
+        // the vacant slot is the next one written
+        assert_eq!(buffer.layout_string(), "[4@1|_@1 2@0 3@0]");
+    }
+
+    #[test]
+    fn test_newest_n_cloned() {
+        let mut buffer = GenerationalBuffer::new(5);
+        for i in 0..8 {
+            buffer.push(i.to_string());
+        }
+        assert_eq!(buffer.newest_n_cloned(2), vec!["6", "7"]);
+        let mut newest: Vec<_> = buffer.iter_rev().take(3).map(|(_, v)| v.clone()).collect();
+        newest.reverse();
+        assert_eq!(buffer.newest_n_cloned(3), newest);
+        assert_eq!(buffer.newest_n_cloned(10).len(), 5);
+        assert!(buffer.newest_n_cloned(0).is_empty());
+    }
+
+    #[test]
+    fn test_partition_valid() {
+        let mut buffer = GenerationalBuffer::new(3);
+        let handles: Vec<_> = (0..5).map(|i| buffer.push(i)).collect();
+        buffer.invalidate(handles[3]);
+        let (valid, stale) = buffer.partition_valid(handles.iter().copied());
+        assert_eq!(valid, vec![handles[2], handles[4]]);
+        assert_eq!(stale, vec![handles[0], handles[1], handles[3]]);
+    }
+
+    #[test]
+    fn test_enumerate_chronological() {
+        let mut buffer = GenerationalBuffer::new(4);
+        let handles: Vec<_> = (0..7).map(|i| buffer.push(i)).collect();
+        buffer.invalidate(handles[4]);
+        let enumerated: Vec<_> = buffer.enumerate_chronological().collect();
+        assert_eq!(enumerated.len(), buffer.len());
+        for ((position, handle, value), (i, (h, v))) in
+            enumerated.into_iter().zip(buffer.iter_chronological().enumerate())
+        {
+            assert_eq!(position, i);
+            assert_eq!(handle, h);
+            assert_eq!(value, v);
+        }
+    }
+
+    #[test]
+    fn test_replace_contents() {
+        let mut buffer = GenerationalBuffer::new(4);
+        let old: Vec<_> = (0..6).map(|i| buffer.push(i)).collect();
+        let generation = buffer.current_generation;
+        buffer.replace_contents([10, 11]);
+        assert_eq!(buffer.current_generation, generation + 1);
+        assert!(old.iter().all(|h| !buffer.is_valid(h)));
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![10, 11]);
+        assert!(buffer.handles().all(|h| h.generation == generation + 1));
+        buffer.check_invariants().unwrap();
+        // only the newest values are kept
+        let (pushed, evicted) = (buffer.total_pushed(), buffer.total_evicted());
+        buffer.replace_contents(20..30);
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![26, 27, 28, 29]);
+        assert_eq!(buffer.total_pushed(), pushed + 10);
+        assert_eq!(buffer.total_evicted(), evicted + 6);
+        assert!(buffer.handles().all(|h| h.generation == generation + 2));
+        assert_eq!(buffer.current_generation, generation + 2);
+        buffer.check_invariants().unwrap();
+        let (_, evicted) = buffer.push_tracking(30);
+        assert_eq!(evicted.map(|h| h.index), Some(0));
+    }
+
+    #[test]
+    fn test_replace_contents_streams() {
+        use std::{
+            cell::Cell,
+            rc::Rc,
+        };
+        struct Counted(Rc<Cell<usize>>);
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() - 1);
+            }
+        }
+        let alive = Rc::new(Cell::new(0));
+        let mut buffer = GenerationalBuffer::new(3);
+        buffer.replace_contents((0..1000).map(|_| {
+            // all values but the last 3 were dropped
+            assert!(alive.get() <= 3);
+            alive.set(alive.get() + 1);
+            Counted(Rc::clone(&alive))
+        }));
+        assert_eq!(alive.get(), 3);
+        assert_eq!(buffer.total_pushed(), 1000);
+        assert_eq!(buffer.total_evicted(), 997);
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_get_with_generation() {
+        let mut buffer = GenerationalBuffer::new(2);
+        let handles: Vec<_> = (0..5).map(|i| buffer.push(i)).collect();
+        for &handle in &handles[3..] {
+            let (_, generation) = buffer.get_with_generation(handle).unwrap();
+            assert_eq!(generation, handle.generation);
+        }
+        assert_eq!(buffer.get_with_generation(handles[4]), Some((&4, 2)));
+        assert_eq!(buffer.get_with_generation(handles[2]), None);
+    }
+
+    #[test]
+    fn test_reserve_exact() {
+        let mut buffer: GenerationalBuffer<String> = GenerationalBuffer::new(37);
+        buffer.reserve_exact();
+        assert_eq!(buffer.allocated_capacity(), 37);
+        assert!(buffer.is_empty());
+        for i in 0..40 {
+            buffer.push(i.to_string());
+        }
+        assert_eq!(buffer.allocated_capacity(), 37);
+    }
+
+    #[test]
+    fn test_try_reserve() {
+        let mut buffer: GenerationalBuffer<u8> = GenerationalBuffer::new(1000);
+        assert!(buffer.try_reserve().is_ok());
+        assert!(buffer.allocated_capacity() >= 1000);
+        let mut huge: GenerationalBuffer<[u8; 1024]> = GenerationalBuffer::new(usize::MAX / 2);
+        assert!(huge.try_reserve().is_err());
+    }
+
+    #[test]
+    fn test_handles_where() {
+        let mut buffer = GenerationalBuffer::new(6);
+        for i in 0..10 {
+            buffer.push(i);
+        }
+        let even: Vec<_> = buffer.handles_where(|v| v % 2 == 0).collect();
+        assert_eq!(even.len(), 3);
+        for handle in even {
+            assert!(buffer.get(handle).is_some_and(|v| v % 2 == 0));
+        }
+        assert_eq!(buffer.handles_where(|&v| v > 100).count(), 0);
+    }
+
+    #[test]
+    fn test_resize_with() {
+        let mut buffer = GenerationalBuffer::new(2);
+        let handles: Vec<_> = (0..3).map(|i| buffer.push(i)).collect();
+        // the ring wrapped in the middle
+        let handles = &handles[1..];
+        buffer.resize_with(4, || -1);
+        buffer.check_invariants().unwrap();
+        assert_eq!(buffer.capacity(), 4);
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(buffer.handles().count(), 4);
+        assert!(buffer.handles().all(|h| buffer.is_valid(h)));
+        assert!(handles.iter().all(|h| buffer.is_valid(h)));
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![1, 2, -1, -1]);
+        assert!(buffer.values_unordered().eq(buffer.values()));
+        // the ring goes on
+        let h = buffer.push(3);
+        assert!(!buffer.is_valid(handles[0]));
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![2, -1, -1, 3]);
+        assert!(buffer.is_valid(h));
+        buffer.check_invariants().unwrap();
+        // growing a normalized buffer keeps the handles
+        let mut buffer = GenerationalBuffer::new(3);
+        let h = buffer.push(0);
+        buffer.resize_with(5, || 9);
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![0, 9, 9]);
+        assert!(buffer.is_valid(h));
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_resize_with_exact_wrap() {
+        let mut buffer = GenerationalBuffer::new(2);
+        let handles = [buffer.push(0), buffer.push(1)];
+        buffer.resize_with(4, || 9);
+        buffer.check_invariants().unwrap();
+        assert!(handles.iter().all(|h| buffer.is_valid(h)));
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![0, 1, 9, 9]);
+        // the oldest entry is the next to be evicted
+        let h = buffer.push(2);
+        assert!(!buffer.is_valid(handles[0]));
+        assert!(buffer.is_valid(handles[1]));
+        assert!(buffer.is_valid(h));
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_resize_with_normalizes() {
+        let mut buffer = GenerationalBuffer::new(4);
+        let handles: Vec<_> = (0..6).map(|i| buffer.push(i)).collect();
+        buffer.invalidate(handles[3]);
+        buffer.resize_with(6, || 0);
+        assert_eq!(buffer.as_contiguous_slice(), Some(&[2, 4, 5, 0, 0][..]));
+        buffer.check_invariants().unwrap();
+        // the handles of the entries are kept
+        for i in [2, 4, 5] {
+            buffer.debug_assert_resolves(handles[i], &(i as i32));
+        }
+        // also when the ring wrapped in the middle, without removal
+        let mut buffer = GenerationalBuffer::new(3);
+        let handles: Vec<_> = (0..5).map(|i| buffer.push(i)).collect();
+        buffer.resize_with(5, || 9);
+        assert_eq!(buffer.as_contiguous_slice(), Some(&[2, 3, 4, 9, 9][..]));
+        for (&handle, i) in handles.iter().zip(0..).skip(2) {
+            buffer.debug_assert_resolves(handle, &i);
+        }
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_resize_with_shrinking() {
+        let mut buffer = GenerationalBuffer::new(5);
+        let handles: Vec<_> = (0..7).map(|i| buffer.push(i)).collect();
+        buffer.resize_with(3, || unreachable!());
+        buffer.check_invariants().unwrap();
+        assert_eq!(buffer.capacity(), 3);
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![4, 5, 6]);
+        assert!(handles[..4].iter().all(|h| !buffer.is_valid(h)));
+        for (&handle, i) in handles[4..].iter().zip(4..) {
+            buffer.debug_assert_resolves(handle, &i);
+        }
+        assert_eq!(buffer.total_evicted(), 4);
+        buffer.push(7);
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![5, 6, 7]);
+    }
+
+    #[test]
+    fn test_slot_changed_between() {
+        let mut buffer = GenerationalBuffer::new(4);
+        let handles: Vec<_> = (0..4).map(|i| buffer.push(i)).collect();
+        let a = buffer.epoch();
+        buffer.push(4); // overwrites the slot 0
+        buffer.push(5); // overwrites the slot 1
+        let b = buffer.epoch();
+        assert!(buffer.slot_changed_between(handles[0], a, b));
+        assert!(buffer.slot_changed_between(handles[1], a, b));
+        assert!(!buffer.slot_changed_between(handles[2], a, b));
+        assert!(!buffer.slot_changed_between(handles[3], a, b));
+        assert!(!buffer.slot_changed_between(handles[0], b, a));
+        // only the last write is known: later writes make the answer
+        // conservative
+        for i in 6..9 {
+            buffer.push(i);
+        }
+        let c = buffer.epoch();
+        assert!(buffer.slot_changed_between(handles[1], a, b));
+        assert!(buffer.slot_changed_between(handles[2], a, b));
+        assert!(buffer.slot_changed_between(handles[2], b, c));
+        assert!(!buffer.slot_changed_between(handles[0], c, buffer.epoch()));
+    }
+
+    #[test]
+    fn test_iter_is_chronological() {
+        let mut buffer = GenerationalBuffer::new(4);
+        let handles: Vec<_> = (0..7).map(|i| buffer.push(i)).collect();
+        buffer.invalidate(handles[4]);
+        let values: Vec<_> = buffer.values().copied().collect();
+        assert_eq!(values, vec![3, 5, 6]);
+        assert_eq!(buffer.handles().collect::<Vec<_>>(), buffer.handles_chronological());
+        assert!(buffer.iter().eq(buffer.iter_chronological()));
+        assert!(buffer.iter().rev().eq(buffer.iter_rev()));
+        assert_eq!(buffer.values().rev().copied().collect::<Vec<_>>(), vec![6, 5, 3]);
+        // mixing both ends
+        let mut iter = buffer.values();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next_back(), Some(&6));
+        assert_eq!(iter.next(), Some(&3));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+        // the order in memory is still available
+        let mut values: Vec<_> = buffer.values_unordered().copied().collect();
+        values.sort();
+        assert_eq!(values, vec![3, 5, 6]);
+    }
+
+    #[test]
+    fn test_accepts_handles_from_capacity() {
+        let mut old: GenerationalBuffer<i32> = GenerationalBuffer::new(4);
+        let handle = old.push(1);
+        let saved_capacity = old.capacity();
+        let mut reloaded = GenerationalBuffer::new(8);
+        reloaded.push(2);
+        assert!(!reloaded.accepts_handles_from_capacity(saved_capacity));
+        assert!(old.accepts_handles_from_capacity(saved_capacity));
+        // the handle would otherwise resolve to an unrelated entry
+        assert_eq!(reloaded.get(handle), Some(&2));
+    }
+
+    #[test]
+    fn test_push_mut() {
+        let mut buffer = GenerationalBuffer::new(2);
+        buffer.push(vec![0]);
+        buffer.push(vec![1]);
+        let (handle, value) = buffer.push_mut(vec![2]);
+        value.push(3);
+        assert_eq!(buffer.get(handle), Some(&vec![2, 3]));
+        assert_eq!(buffer.len(), 2);
+    }
+
+    #[test]
+    fn test_drain_where() {
+        let mut buffer = GenerationalBuffer::new(5);
+        let handles: Vec<_> = (0..7).map(|i| buffer.push(i)).collect();
+        let drained = buffer.drain_where(|v| v % 2 == 0);
+        assert_eq!(
+            drained,
+            vec![(handles[2], 2), (handles[4], 4), (handles[6], 6)]
+        );
+        assert!(drained.iter().all(|(h, _)| !buffer.is_valid(h)));
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![3, 5]);
+        assert!(buffer.is_valid(handles[3]));
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_try_shrink() {
+        let mut buffer = GenerationalBuffer::new(10);
+        for i in 0..4 {
+            buffer.push(i);
+        }
+        assert_eq!(buffer.try_shrink(2), Err(2));
+        assert_eq!(buffer.capacity(), 10);
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(buffer.try_shrink(4), Ok(()));
+        assert_eq!(buffer.capacity(), 4);
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![0, 1, 2, 3]);
+        buffer.check_invariants().unwrap();
+        buffer.shrink_capacity_keeping_newest(3);
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![1, 2, 3]);
+        assert_eq!(buffer.try_shrink(0), Err(2));
+    }
+
+    #[test]
+    fn test_remove_stable() {
+        let mut buffer = GenerationalBuffer::new(5);
+        let handles: Vec<_> = (0..7).map(|i| buffer.push(i)).collect();
+        assert_eq!(buffer.remove_stable(handles[4]), Some(4));
+        buffer.check_invariants().unwrap();
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![2, 3, 5, 6]);
+        assert!(buffer.is_valid(handles[2]));
+        assert!(buffer.is_valid(handles[3]));
+        assert!(!buffer.is_valid(handles[4]));
+        assert!(handles[5..].iter().all(|h| buffer.is_valid(h)));
+        assert_eq!(buffer.remove_stable(handles[4]), None);
+        // the freed slot is the next one written
+        buffer.push(7);
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![2, 3, 5, 6, 7]);
+        buffer.push(8);
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![3, 5, 6, 7, 8]);
+        assert!(!handles.iter().any(|h| buffer.get(h) == Some(&7)));
+        buffer.check_invariants().unwrap();
+        // before the ring wraps
+        let mut buffer = GenerationalBuffer::new(5);
+        let handles: Vec<_> = (0..3).map(|i| buffer.push(i)).collect();
+        buffer.remove_stable(handles[0]);
+        buffer.check_invariants().unwrap();
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![1, 2]);
+        buffer.push(3);
+        assert_eq!(buffer.project_to_vec(|&v| v), vec![1, 2, 3]);
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_count_where() {
+        let mut buffer = GenerationalBuffer::new(5);
+        for i in 0..12 {
+            buffer.push(i);
+        }
+        assert_eq!(buffer.count_where(|v| v % 2 == 0), 2);
+        assert_eq!(buffer.count_where(|v| v % 2 == 1), 3);
+        assert_eq!(buffer.count_where(|&v| v < 7), 0);
+    }
+
+    #[test]
+    fn test_any_all() {
+        let mut buffer = GenerationalBuffer::new(3);
+        assert!(!buffer.any(|_: &i32| true));
+        assert!(buffer.all(|_| false));
+        for i in 0..5 {
+            buffer.push(i);
+        }
+        assert!(buffer.any(|&v| v == 3));
+        assert!(!buffer.any(|&v| v == 1));
+        assert!(buffer.all(|&v| v >= 2));
+        assert!(!buffer.all(|&v| v >= 3));
+        // short-circuit
+        let mut checked = 0;
+        assert!(buffer.any(|_| {
+            checked += 1;
+            true
+        }));
+        assert_eq!(checked, 1);
+    }
+
+    #[test]
+    fn test_current_handle_and_generation() {
+        let mut buffer = GenerationalBuffer::new(3);
+        let handles: Vec<_> = (0..4).map(|i| buffer.push(i)).collect();
+        assert_eq!(buffer.current_handle_and_generation(0), Some((handles[3], 1)));
+        assert_eq!(buffer.current_handle_and_generation(2), Some((handles[2], 0)));
+        assert_eq!(buffer.current_handle_and_generation(3), None);
+        buffer.invalidate(handles[1]);
+        assert_eq!(buffer.current_handle_and_generation(1), None);
+    }
+
+    #[test]
+    fn test_resolve_all() {
+        let mut buffer = GenerationalBuffer::new(3);
+        let handles: Vec<_> = (0..5).map(|i| buffer.push(i)).collect();
+        buffer.invalidate(handles[3]);
+        let resolved: Vec<_> = buffer.resolve_all(handles.iter().rev().copied()).collect();
+        assert_eq!(resolved, vec![(handles[4], &4), (handles[2], &2)]);
+    }
+
+    #[test]
+    fn test_rotate() {
+        fn values(buffer: &GenerationalBuffer<i32>) -> Vec<i32> {
+            buffer.iter_chronological().map(|(_, &v)| v).collect()
+        }
+        let mut buffer = GenerationalBuffer::new(4);
+        let handles: Vec<_> = (0..6).map(|i| buffer.push(i)).collect();
+        assert_eq!(values(&buffer), vec![2, 3, 4, 5]);
+        buffer.rotate(1);
+        buffer.check_invariants().unwrap();
+        assert_eq!(values(&buffer), vec![3, 4, 5, 2]);
+        buffer.rotate(-3);
+        buffer.check_invariants().unwrap();
+        assert_eq!(values(&buffer), vec![4, 5, 2, 3]);
+        buffer.rotate(8);
+        assert_eq!(values(&buffer), vec![4, 5, 2, 3]);
+        for (i, &handle) in handles.iter().enumerate().skip(2) {
+            assert_eq!(buffer.get(handle), Some(&(i as i32)));
+        }
+        // the next push evicts the new oldest entry
+        let h6 = buffer.push(6);
+        assert_eq!(values(&buffer), vec![5, 2, 3, 6]);
+        assert!(!buffer.is_valid(handles[4]));
+        assert!(h6 != handles[4] && h6 != handles[0]);
+        buffer.check_invariants().unwrap();
+        for i in 7..12 {
+            buffer.push(i);
+            buffer.check_invariants().unwrap();
+        }
+        assert_eq!(values(&buffer), vec![8, 9, 10, 11]);
+        assert!(handles.iter().all(|&h| !buffer.is_valid(h)));
+    }
+
+    #[test]
+    fn test_rotate_not_full() {
+        let mut buffer = GenerationalBuffer::new(4);
+        let h0 = buffer.push(0);
+        let h1 = buffer.push(1);
+        buffer.rotate(1);
+        buffer.check_invariants().unwrap();
+        assert_eq!(buffer.values().copied().collect::<Vec<_>>(), vec![1, 0]);
+        let h2 = buffer.push(2);
+        assert_eq!(buffer.values().copied().collect::<Vec<_>>(), vec![1, 0, 2]);
+        buffer.check_invariants().unwrap();
+        assert_eq!(buffer.get(h0), Some(&0));
+        assert_eq!(buffer.get(h1), Some(&1));
         assert_eq!(buffer.get(h2), Some(&2));
-        assert_eq!(buffer.get(h3), Some(&3));
+        // only the entries are rotated, the buffer still fills up
+        buffer.push(3);
+        buffer.push(4);
+        assert_eq!(buffer.values().copied().collect::<Vec<_>>(), vec![0, 2, 3, 4]);
+        assert!(!buffer.is_valid(h1));
+        buffer.check_invariants().unwrap();
+    }
+
+    #[test]
+    fn test_rotate_huge_capacity() {
+        for capacity in [usize::MAX / 2 + 3, usize::MAX] {
+            let mut buffer = GenerationalBuffer::new(capacity);
+            let handles: Vec<_> = (0..3).map(|i| buffer.push(i)).collect();
+            buffer.rotate(-1);
+            assert_eq!(buffer.values().copied().collect::<Vec<_>>(), vec![2, 0, 1]);
+            buffer.rotate(isize::MIN);
+            assert_eq!(buffer.values().copied().collect::<Vec<_>>(), vec![0, 1, 2]);
+            for (&handle, i) in handles.iter().zip(0..) {
+                buffer.debug_assert_resolves(handle, &i);
+            }
+            buffer.check_invariants().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_split_around() {
+        let mut buffer = GenerationalBuffer::new(5);
+        let handles: Vec<_> = (0..8).map(|i| buffer.push(i)).collect();
+        // the buffer holds 3..8, its oldest entry being at index 3
+        let (older, newer) = buffer.split_around(handles[5]).unwrap();
+        assert_eq!(older, vec![&3, &4]);
+        assert_eq!(newer, vec![&6, &7]);
+        let (older, newer) = buffer.split_around(handles[3]).unwrap();
+        assert!(older.is_empty());
+        assert_eq!(newer, vec![&4, &5, &6, &7]);
+        let (older, newer) = buffer.split_around(handles[7]).unwrap();
+        assert_eq!(older, vec![&3, &4, &5, &6]);
+        assert!(newer.is_empty());
+        assert_eq!(buffer.split_around(handles[2]), None);
     }
 }
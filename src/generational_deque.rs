@@ -0,0 +1,138 @@
+use {
+    crate::{
+        Generation,
+        Handle,
+    },
+    std::collections::VecDeque,
+};
+
+/// A FIFO queue with generational IDs, and no fixed capacity
+///
+/// Pushing to the back returns a `Handle` that can be used to access the
+/// value later, until it's popped from the front.
+///
+/// Entries get increasing ids, which are used as handle indices. The
+/// generation is only incremented when the ids wrap.
+pub struct GenerationalDeque<T> {
+    entries: VecDeque<(u32, T)>,
+    next_id: usize,
+    current_generation: u32,
+}
+
+impl<T> GenerationalDeque<T> {
+    /// Creates a new empty deque
+    pub fn new() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            next_id: 0,
+            current_generation: 0,
+        }
+    }
+
+    /// Returns the current number of entries in the deque
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the deque is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Inserts a value at the back of the deque and returns a handle to it
+    pub fn push_back(&mut self, value: T) -> Handle<T> {
+        let handle = Handle::new(self.next_id, self.current_generation);
+        self.entries.push_back((self.current_generation, value));
+        self.next_id = self.next_id.wrapping_add(1);
+        if self.next_id == 0 {
+            self.current_generation = self.current_generation.wrapping_next();
+        }
+        handle
+    }
+
+    /// Removes the oldest entry and returns its value, invalidating its handle
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.entries.pop_front().map(|(_, value)| value)
+    }
+
+    /// Gets a reference to the value associated with the handle
+    pub fn get(&self, handle: Handle<T>) -> Option<&T> {
+        let position = self.position(handle)?;
+        Some(&self.entries[position].1)
+    }
+
+    /// Gets a mutable reference to the value associated with the handle
+    pub fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        let position = self.position(handle)?;
+        Some(&mut self.entries[position].1)
+    }
+
+    /// Checks if a handle is still valid (points to existing data)
+    pub fn is_valid(&self, handle: Handle<T>) -> bool {
+        self.position(handle).is_some()
+    }
+
+    /// Returns the position in the deque of the entry of the handle
+    fn position(&self, handle: Handle<T>) -> Option<usize> {
+        let front_id = self.next_id.wrapping_sub(self.entries.len());
+        let position = handle.index.wrapping_sub(front_id);
+        self.entries
+            .get(position)
+            .filter(|(generation, _)| *generation == handle.generation)
+            .map(|_| position)
+    }
+}
+
+impl<T> Default for GenerationalDeque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fifo_order() {
+        let mut deque = GenerationalDeque::new();
+        for i in 0..5 {
+            deque.push_back(i);
+        }
+        assert_eq!(deque.len(), 5);
+        let popped: Vec<i32> = std::iter::from_fn(|| deque.pop_front()).collect();
+        assert_eq!(popped, vec![0, 1, 2, 3, 4]);
+        assert!(deque.is_empty());
+    }
+
+    #[test]
+    fn test_invalidation_on_pop() {
+        let mut deque = GenerationalDeque::new();
+        let h1 = deque.push_back("a");
+        let h2 = deque.push_back("b");
+        assert_eq!(deque.get(h1), Some(&"a"));
+        assert_eq!(deque.pop_front(), Some("a"));
+        assert!(!deque.is_valid(h1));
+        assert_eq!(deque.get(h2), Some(&"b"));
+        let h3 = deque.push_back("c");
+        *deque.get_mut(h3).unwrap() = "C";
+        assert_eq!(deque.get(h3), Some(&"C"));
+        assert_eq!(deque.pop_front(), Some("b"));
+        assert!(!deque.is_valid(h2));
+        assert!(deque.is_valid(h3));
+    }
+
+    #[test]
+    fn test_id_wrap() {
+        let mut deque = GenerationalDeque::new();
+        deque.next_id = usize::MAX;
+        let h1 = deque.push_back(1);
+        let h2 = deque.push_back(2);
+        assert_eq!(h2.index, 0);
+        assert_eq!(deque.get(h1), Some(&1));
+        assert_eq!(deque.get(h2), Some(&2));
+        deque.pop_front();
+        assert!(!deque.is_valid(h1));
+        assert!(deque.is_valid(h2));
+    }
+}
@@ -0,0 +1,180 @@
+use {
+    crate::Generation,
+    std::{
+        fmt,
+        hash::{
+            Hash,
+            Hasher,
+        },
+        marker::PhantomData,
+    },
+};
+
+/// A handle that combines an index with a generation counter.
+///
+/// The handle is typed according to the type of data it refers to,
+/// but doesn't hold it.
+#[derive(Debug)]
+pub struct Handle<T, G: Generation = u32> {
+    pub(crate) index: usize,
+    pub(crate) generation: G,
+    phantom: PhantomData<T>,
+}
+
+impl<T, G: Generation> Handle<T, G> {
+    pub(crate) fn new(index: usize, generation: G) -> Self {
+        Self {
+            index,
+            generation,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns a handle which is invalid for any buffer, to initialize
+    /// fields before a real handle is known
+    ///
+    /// Its index is `usize::MAX`, which no buffer can have a slot at.
+    pub fn dangling() -> Self {
+        Self::new(usize::MAX, G::ZERO)
+    }
+
+    /// Returns the index of the slot the handle points to
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the generation at which the entry was written
+    pub const fn generation(&self) -> G {
+        self.generation
+    }
+
+    /// Returns the signed number of generations from `a` to `b`
+    ///
+    /// The result is positive when `b` is more recent than `a`. The
+    /// difference is computed with wrapping arithmetic, so handles on
+    /// each side of the generation wrap are still correctly ordered as
+    /// long as they're less than half the generation range apart.
+    ///
+    /// This works with stale handles too.
+    pub fn generation_distance(a: Handle<T, G>, b: Handle<T, G>) -> i64 {
+        a.generation.wrapping_distance(b.generation)
+    }
+
+    /// Converts the handle into a handle to the corresponding entry of
+    /// a buffer built with [`GenerationalBuffer::map`](crate::GenerationalBuffer::map)
+    ///
+    /// The returned handle is only meaningful against a buffer produced by
+    /// `map` from the buffer this handle comes from.
+    pub fn remap_handle<U>(handle: Handle<T, G>) -> Handle<U, G> {
+        Handle::new(handle.index, handle.generation)
+    }
+
+    /// Compares the index and generation of handles to entries of
+    /// possibly different types
+    ///
+    /// Contrary to `==`, this makes it possible to compare handles
+    /// coming from different buffers, which is rarely meaningful.
+    pub fn raw_eq<U>(&self, other: &Handle<U, G>) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+
+    /// Returns true if both handles point to the same slot, whatever
+    /// their generations
+    pub fn same_slot(&self, other: &Handle<T, G>) -> bool {
+        self.index == other.index
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T, G> arbitrary::Arbitrary<'a> for Handle<T, G>
+where
+    G: Generation + arbitrary::Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::new(usize::arbitrary(u)?, G::arbitrary(u)?))
+    }
+}
+
+impl<O, G: Generation> PartialEq for Handle<O, G> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+impl<O, G: Generation> Eq for Handle<O, G> {}
+/// Displays the handle as `#index@ggeneration`, eg `#3@g2`
+impl<O, G: Generation> fmt::Display for Handle<O, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}@g{}", self.index, self.generation.to_u64())
+    }
+}
+impl<O, G: Generation> Hash for Handle<O, G> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+impl<O, G: Generation> Clone for Handle<O, G> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<O, G: Generation> Copy for Handle<O, G> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_eq() {
+        let a: Handle<i32> = Handle::new(3, 7);
+        let b: Handle<String> = Handle::new(3, 7);
+        let c: Handle<String> = Handle::new(3, 8);
+        assert!(a.raw_eq(&b));
+        assert!(b.raw_eq(&a));
+        assert!(!a.raw_eq(&c));
+    }
+
+    #[test]
+    fn test_same_slot() {
+        let a: Handle<i32> = Handle::new(2, 0);
+        let b: Handle<i32> = Handle::new(2, 5);
+        let c: Handle<i32> = Handle::new(1, 0);
+        assert!(a.same_slot(&b));
+        assert_ne!(a, b);
+        assert!(!a.same_slot(&c));
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut buffer = crate::GenerationalBuffer::new(2);
+        let handles: Vec<_> = (0..3).map(|i| buffer.push(i)).collect();
+        assert_eq!(handles[1].index(), 1);
+        assert_eq!(handles[1].generation(), 0);
+        assert_eq!(handles[2].index(), 0);
+        assert_eq!(handles[2].generation(), 1);
+    }
+
+    #[test]
+    fn test_display() {
+        struct NotDisplay;
+        let handle: Handle<NotDisplay> = Handle::new(3, 2);
+        assert_eq!(handle.to_string(), "#3@g2");
+        let handle: Handle<NotDisplay, u16> = Handle::new(0, u16::MAX);
+        assert_eq!(format!("{handle}"), "#0@g65535");
+    }
+
+    #[test]
+    fn test_dangling() {
+        let mut buffer = crate::GenerationalBuffer::new(3);
+        for i in 0..5 {
+            buffer.push(i);
+        }
+        let dangling = Handle::dangling();
+        assert!(!buffer.is_valid(dangling));
+        assert_eq!(buffer.get(dangling), None);
+        assert!(!buffer.validate_handle_shape(dangling));
+        let mut buffer: crate::GenerationalBuffer<i32> = crate::GenerationalBuffer::new(usize::MAX);
+        buffer.push(0);
+        assert!(!buffer.is_valid(Handle::dangling()));
+    }
+}
@@ -0,0 +1,103 @@
+use {
+    crate::{
+        Generation,
+        GenerationalBuffer,
+        Handle,
+    },
+    std::collections::HashSet,
+};
+
+/// A collection of handles to entries of a buffer, in insertion order,
+/// with the bookkeeping needed to forget the stale ones
+///
+/// The handles are also kept in a hash set, so that inserting is O(1).
+pub struct HandleSet<T, G: Generation = u32> {
+    handles: Vec<Handle<T, G>>,
+    members: HashSet<Handle<T, G>>,
+}
+
+impl<T, G: Generation> HandleSet<T, G> {
+    /// Creates an empty set
+    pub fn new() -> Self {
+        Self {
+            handles: Vec::new(),
+            members: HashSet::new(),
+        }
+    }
+
+    /// Returns the number of handles in the set, including stale ones
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Returns true if the set holds no handle
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Adds a handle, returning false if it was already in the set
+    pub fn insert(&mut self, handle: Handle<T, G>) -> bool {
+        if !self.members.insert(handle) {
+            return false;
+        }
+        self.handles.push(handle);
+        true
+    }
+
+    /// Removes the handles which aren't valid anymore in the buffer
+    pub fn retain_valid(&mut self, buffer: &GenerationalBuffer<T, G>) {
+        let members = &mut self.members;
+        self.handles.retain(|&handle| {
+            let valid = buffer.is_valid(handle);
+            if !valid {
+                members.remove(&handle);
+            }
+            valid
+        });
+    }
+
+    /// Returns an iterator over the handles still valid in the buffer,
+    /// in insertion order
+    pub fn valid_iter<'a>(
+        &'a self,
+        buffer: &'a GenerationalBuffer<T, G>,
+    ) -> impl Iterator<Item = Handle<T, G>> + 'a {
+        self.handles
+            .iter()
+            .copied()
+            .filter(|&handle| buffer.is_valid(handle))
+    }
+}
+
+impl<T, G: Generation> Default for HandleSet<T, G> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retain_valid() {
+        let mut buffer = GenerationalBuffer::new(3);
+        let mut set = HandleSet::new();
+        for i in 0..3 {
+            assert!(set.insert(buffer.push(i)));
+        }
+        let first = set.valid_iter(&buffer).next().unwrap();
+        assert!(!set.insert(first));
+        buffer.push(3);
+        buffer.push(4);
+        assert_eq!(set.valid_iter(&buffer).count(), 1);
+        assert_eq!(set.len(), 3);
+        set.retain_valid(&buffer);
+        assert_eq!(set.len(), 1);
+        let remaining: Vec<_> = set.valid_iter(&buffer).collect();
+        assert_eq!(buffer.get(remaining[0]), Some(&2));
+        assert!(!set.insert(remaining[0]));
+        assert!(set.insert(first));
+        assert_eq!(set.len(), 2);
+    }
+}
@@ -0,0 +1,211 @@
+use {
+    crate::{
+        Generation,
+        Handle,
+        slot::Slot,
+    },
+    std::{
+        collections::VecDeque,
+        iter::{
+            Enumerate,
+            FusedIterator,
+        },
+        ops::Range,
+        slice,
+    },
+};
+
+/// An iterator over the entries of a buffer with their handles,
+/// from the oldest to the newest
+///
+/// Created by [`GenerationalBuffer::iter`](crate::GenerationalBuffer::iter),
+/// or by [`GenerationalBuffer::iter_unordered`](crate::GenerationalBuffer::iter_unordered)
+/// for the order of the values in memory.
+pub struct Iter<'a, T, G: Generation = u32> {
+    // the values are stored in two parts, each one given with the
+    // position of its first value
+    first: Enumerate<slice::Iter<'a, T>>,
+    first_offset: usize,
+    second: Enumerate<slice::Iter<'a, T>>,
+    second_offset: usize,
+    owners: &'a VecDeque<usize>,
+    slots: &'a [Slot<G>],
+}
+
+impl<'a, T, G: Generation> Iter<'a, T, G> {
+    /// Iterates over the values whose positions are in the range, which
+    /// must be within the values
+    pub(crate) fn chronological(
+        values: &'a VecDeque<T>,
+        owners: &'a VecDeque<usize>,
+        slots: &'a [Slot<G>],
+        range: Range<usize>,
+    ) -> Self {
+        let (older, newer) = values.as_slices();
+        let split = older.len();
+        let older = &older[range.start.min(split)..range.end.min(split)];
+        let newer = &newer[range.start.saturating_sub(split)..range.end.saturating_sub(split)];
+        Self {
+            first: older.iter().enumerate(),
+            first_offset: range.start.min(split),
+            second: newer.iter().enumerate(),
+            second_offset: range.start.max(split),
+            owners,
+            slots,
+        }
+    }
+
+    /// Iterates over all values, in the order of their addresses
+    pub(crate) fn physical(
+        values: &'a VecDeque<T>,
+        owners: &'a VecDeque<usize>,
+        slots: &'a [Slot<G>],
+    ) -> Self {
+        // the part holding the oldest values is after the other one
+        // in memory when the storage wraps
+        let (older, newer) = values.as_slices();
+        Self {
+            first: newer.iter().enumerate(),
+            first_offset: older.len(),
+            second: older.iter().enumerate(),
+            second_offset: 0,
+            owners,
+            slots,
+        }
+    }
+
+    /// Returns the entry at the given position, with its handle
+    fn entry(&self, position: usize, value: &'a T) -> (Handle<T, G>, &'a T) {
+        let index = self.owners[position];
+        (Handle::new(index, self.slots[index].generation), value)
+    }
+}
+
+impl<'a, T, G: Generation> Iterator for Iter<'a, T, G> {
+    type Item = (Handle<T, G>, &'a T);
+    fn next(&mut self) -> Option<Self::Item> {
+        let (position, value) = match self.first.next() {
+            Some((i, value)) => (self.first_offset + i, value),
+            None => {
+                let (i, value) = self.second.next()?;
+                (self.second_offset + i, value)
+            }
+        };
+        Some(self.entry(position, value))
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.first.len() + self.second.len();
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T, G: Generation> DoubleEndedIterator for Iter<'_, T, G> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (position, value) = match self.second.next_back() {
+            Some((i, value)) => (self.second_offset + i, value),
+            None => {
+                let (i, value) = self.first.next_back()?;
+                (self.first_offset + i, value)
+            }
+        };
+        Some(self.entry(position, value))
+    }
+}
+
+impl<T, G: Generation> ExactSizeIterator for Iter<'_, T, G> {}
+
+impl<T, G: Generation> FusedIterator for Iter<'_, T, G> {}
+
+/// An iterator over the entries of a buffer, from the oldest to the newest
+///
+/// Created by [`GenerationalBuffer::values`](crate::GenerationalBuffer::values)
+/// or [`GenerationalBuffer::values_unordered`](crate::GenerationalBuffer::values_unordered).
+pub struct Values<'a, T, G: Generation = u32> {
+    iter: Iter<'a, T, G>,
+}
+
+impl<'a, T, G: Generation> Values<'a, T, G> {
+    pub(crate) fn new(iter: Iter<'a, T, G>) -> Self {
+        Self { iter }
+    }
+}
+
+impl<'a, T, G: Generation> Iterator for Values<'a, T, G> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(_, value)| value)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T, G: Generation> DoubleEndedIterator for Values<'_, T, G> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(_, value)| value)
+    }
+}
+
+impl<T, G: Generation> ExactSizeIterator for Values<'_, T, G> {}
+
+impl<T, G: Generation> FusedIterator for Values<'_, T, G> {}
+
+/// An iterator over the valid handles of a buffer, from the oldest to the newest
+///
+/// Created by [`GenerationalBuffer::handles`](crate::GenerationalBuffer::handles)
+/// or [`GenerationalBuffer::handles_unordered`](crate::GenerationalBuffer::handles_unordered).
+pub struct Handles<'a, T, G: Generation = u32> {
+    iter: Iter<'a, T, G>,
+}
+
+impl<'a, T, G: Generation> Handles<'a, T, G> {
+    pub(crate) fn new(iter: Iter<'a, T, G>) -> Self {
+        Self { iter }
+    }
+}
+
+impl<T, G: Generation> Iterator for Handles<'_, T, G> {
+    type Item = Handle<T, G>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|(handle, _)| handle)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T, G: Generation> DoubleEndedIterator for Handles<'_, T, G> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(handle, _)| handle)
+    }
+}
+
+impl<T, G: Generation> ExactSizeIterator for Handles<'_, T, G> {}
+
+impl<T, G: Generation> FusedIterator for Handles<'_, T, G> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::GenerationalBuffer;
+
+    #[test]
+    fn test_fused() {
+        let mut buffer = GenerationalBuffer::new(3);
+        for i in 0..4 {
+            buffer.push(i);
+        }
+        let mut values = buffer.values();
+        assert_eq!(values.by_ref().count(), 3);
+        for _ in 0..3 {
+            assert_eq!(values.next(), None);
+        }
+        let mut handles = buffer.handles();
+        handles.by_ref().for_each(drop);
+        assert_eq!(handles.next(), None);
+        assert_eq!(handles.next_back(), None);
+        let mut iter = buffer.iter_unordered();
+        iter.by_ref().for_each(drop);
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+    }
+}
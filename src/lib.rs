@@ -1,8 +1,9 @@
 //! A ring buffer returning generational handles on insertion, so that
 //! you can check if an item has been replaced since you got the handle.
 //!
-//! This is safe and efficient, the storage is a simple vector not taking
-//! more space than a minimal ring buffer.
+//! This is safe and efficient: the values are stored in chronological order,
+//! in a `VecDeque`, while handles point to slots, each one holding the
+//! generation it was written at and the position of its value.
 //!
 //! ```
 //! let mut buffer = generational_buffer::GenerationalBuffer::new(2);
@@ -32,6 +33,33 @@
 //! assert!(!buffer.is_valid(h3)); // h3 should be invalid now
 //! ```
 
+mod capacity;
+mod epoch;
+mod errors;
+mod generation;
 mod generational_buffer;
+mod generational_deque;
+mod handle;
+mod handle_set;
+mod iter;
+mod shards;
+mod slot;
+mod stats;
+mod summing_buffer;
+mod watcher;
 
-pub use generational_buffer::*;
+pub use {
+    capacity::*,
+    epoch::*,
+    errors::*,
+    generation::*,
+    generational_buffer::*,
+    generational_deque::*,
+    handle::*,
+    handle_set::*,
+    iter::*,
+    shards::*,
+    stats::*,
+    summing_buffer::*,
+    watcher::*,
+};
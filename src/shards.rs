@@ -0,0 +1,56 @@
+use {
+    crate::GenerationalBuffer,
+    std::hash::{
+        DefaultHasher,
+        Hash,
+        Hasher,
+    },
+};
+
+impl<T> GenerationalBuffer<T> {
+    /// Creates `shard_count` empty buffers of the same capacity
+    ///
+    /// To ingest data in parallel, each key is sent to the buffer given by
+    /// [`shard_for`], so that all entries of a key are in the same buffer.
+    /// Handles are only valid for the shard they come from.
+    pub fn new_shards(shard_count: usize, per_shard_capacity: usize) -> Vec<Self> {
+        (0..shard_count)
+            .map(|_| Self::new(per_shard_capacity))
+            .collect()
+    }
+}
+
+/// Returns the index of the shard holding the entries of the key, among
+/// `shard_count` shards
+///
+/// The same key always gives the same shard in a program, but the hash
+/// isn't guaranteed to stay the same across Rust versions, so shard indices
+/// shouldn't be persisted.
+///
+/// # Panics
+///
+/// Panics if `shard_count` is 0.
+pub fn shard_for<K: Hash + ?Sized>(key: &K, shard_count: usize) -> usize {
+    assert!(shard_count > 0, "there must be at least one shard");
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    (hasher.finish() % shard_count as u64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_shards() {
+        let mut shards: Vec<GenerationalBuffer<&str>> = GenerationalBuffer::new_shards(4, 8);
+        assert_eq!(shards.len(), 4);
+        assert!(shards.iter().all(|shard| shard.is_empty() && shard.capacity() == 8));
+        for key in ["a", "b", "c", "a"] {
+            shards[shard_for(key, 4)].push(key);
+        }
+        assert_eq!(shards.iter().map(GenerationalBuffer::len).sum::<usize>(), 4);
+        assert_eq!(shards[shard_for("a", 4)].count_where(|&k| k == "a"), 2);
+        assert_eq!(shard_for("a", 4), shard_for(&"a".to_string()[..], 4));
+    }
+}
@@ -0,0 +1,28 @@
+use crate::Generation;
+
+/// What a handle points to: the generation of the last write of the slot,
+/// and the position of its value when it holds one
+///
+/// A slot is vacant when its entry was removed or evicted, until it's
+/// reused for another value.
+#[derive(Debug)]
+pub(crate) struct Slot<G: Generation> {
+    pub(crate) generation: G,
+    pub(crate) live: bool,
+    /// The position of the value, counted from the `front` of the buffer
+    /// with wrapping arithmetic
+    pub(crate) position: usize,
+    /// The number of values pushed before the last write of the slot
+    pub(crate) pushed: u64,
+}
+
+impl<G: Generation> Slot<G> {
+    /// Returns true if the slot holds a value written at this generation
+    ///
+    /// Both conditions are evaluated, without short-circuit, so that the
+    /// check compiles to a straight-line path.
+    #[inline]
+    pub(crate) fn holds(&self, generation: G) -> bool {
+        (self.generation == generation) & self.live
+    }
+}
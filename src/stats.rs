@@ -0,0 +1,64 @@
+use crate::{
+    Generation,
+    GenerationalBuffer,
+};
+
+/// Summary statistics over the live entries of a buffer
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    /// The smallest live value
+    pub min: f64,
+    /// The largest live value
+    pub max: f64,
+    /// The arithmetic mean of all live values
+    pub mean: f64,
+    /// The number of live values, which is the `len()` of the buffer
+    pub count: usize,
+}
+
+impl<T: Into<f64> + Copy, G: Generation> GenerationalBuffer<T, G> {
+    /// Computes the min, max and mean of the entries,
+    /// or returns `None` if the buffer is empty
+    pub fn stats(&self) -> Option<Stats> {
+        let mut values = self.values().map(|&v| v.into());
+        let first = values.next()?;
+        let mut stats = Stats {
+            min: first,
+            max: first,
+            mean: 0.0,
+            count: 1,
+        };
+        let mut sum = first;
+        for v in values {
+            stats.min = stats.min.min(v);
+            stats.max = stats.max.max(v);
+            sum += v;
+            stats.count += 1;
+        }
+        stats.mean = sum / stats.count as f64;
+        Some(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut buffer: GenerationalBuffer<u8> = GenerationalBuffer::new(4);
+        assert_eq!(buffer.stats(), None);
+        for v in [100, 3, 9, 1, 4, 6] {
+            buffer.push(v);
+        }
+        assert_eq!(
+            buffer.stats(),
+            Some(Stats {
+                min: 1.0,
+                max: 9.0,
+                mean: 5.0,
+                count: 4,
+            })
+        );
+    }
+}
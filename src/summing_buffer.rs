@@ -0,0 +1,88 @@
+use {
+    crate::{
+        Capacity,
+        GenerationalBuffer,
+        Handle,
+    },
+    std::ops::{
+        Add,
+        Deref,
+        Sub,
+    },
+};
+
+/// A generational buffer maintaining the sum of its entries
+///
+/// The sum is updated on each push, by adding the new value and
+/// subtracting the evicted one, so that reading it is O(1).
+/// With floating point values, rounding errors may accumulate.
+///
+/// The underlying buffer is available for reading through `Deref`.
+pub struct SummingBuffer<T> {
+    buffer: GenerationalBuffer<T>,
+    sum: T,
+}
+
+impl<T> SummingBuffer<T>
+where
+    T: Add<Output = T> + Sub<Output = T> + Copy + Default,
+{
+    /// Creates a new summing buffer with the specified capacity
+    ///
+    /// Minimum capacity is 1.
+    pub fn new<C: Into<Capacity>>(max_capacity: C) -> Self {
+        Self {
+            buffer: GenerationalBuffer::new(max_capacity),
+            sum: T::default(),
+        }
+    }
+
+    /// Returns the sum of all entries
+    pub fn sum(&self) -> T {
+        self.sum
+    }
+
+    /// Inserts a value into the buffer and returns a handle to it.
+    ///
+    /// This removes the oldest entry if the buffer is full.
+    pub fn push(&mut self, value: T) -> Handle<T> {
+        let (handle, evicted) = self.buffer.push_evicting(value);
+        self.sum = self.sum + value;
+        if let Some((_, evicted)) = evicted {
+            self.sum = self.sum - evicted;
+        }
+        handle
+    }
+
+    /// Clear the buffer, removing all entries and rendering all
+    /// existing handles invalid.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.sum = T::default();
+    }
+}
+
+impl<T> Deref for SummingBuffer<T> {
+    type Target = GenerationalBuffer<T>;
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sum_through_wrap() {
+        let mut buffer: SummingBuffer<i64> = SummingBuffer::new(3);
+        assert_eq!(buffer.sum(), 0);
+        for i in 1..=10 {
+            buffer.push(i * i);
+            assert_eq!(buffer.sum(), buffer.values().sum::<i64>());
+        }
+        assert_eq!(buffer.sum(), 64 + 81 + 100);
+        buffer.clear();
+        assert_eq!(buffer.sum(), 0);
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{
+    Generation,
+    GenerationalBuffer,
+    Handle,
+};
+
+/// Records the generation of a handle's slot, to later know whether the
+/// slot was overwritten
+///
+/// Unlike [`GenerationalBuffer::is_valid`], this is about the slot changing
+/// since it was looked at: removing the entry (eg with `invalidate`) doesn't
+/// change the slot's generation, while a watcher created for a stale handle
+/// still detects the next overwrite of the slot. In-place modifications, eg
+/// with `get_mut` or `replace`, aren't detected.
+pub struct Watcher<T, G: Generation = u32> {
+    handle: Handle<T, G>,
+    generation: Option<G>,
+}
+
+impl<T, G: Generation> Watcher<T, G> {
+    /// Creates a watcher of the slot of the handle, recording its
+    /// current generation
+    pub fn new(buffer: &GenerationalBuffer<T, G>, handle: Handle<T, G>) -> Self {
+        Self {
+            handle,
+            generation: buffer.slot_generation(handle.index()),
+        }
+    }
+
+    /// Returns the handle the watcher was created with
+    pub fn handle(&self) -> Handle<T, G> {
+        self.handle
+    }
+
+    /// Returns true if the slot's generation isn't the recorded one anymore,
+    /// that is the slot was overwritten, or the buffer cleared
+    pub fn has_changed(&self, buffer: &GenerationalBuffer<T, G>) -> bool {
+        buffer.slot_generation(self.handle.index()) != self.generation
+    }
+
+    /// Records the current generation of the slot, so that `has_changed`
+    /// only reports later changes
+    pub fn mark_seen(&mut self, buffer: &GenerationalBuffer<T, G>) {
+        self.generation = buffer.slot_generation(self.handle.index());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_changed() {
+        let mut buffer = GenerationalBuffer::new(2);
+        let h1 = buffer.push(1);
+        let watcher = Watcher::new(&buffer, h1);
+        buffer.push(2);
+        *buffer.get_mut(watcher.handle()).unwrap() = 10;
+        assert!(!watcher.has_changed(&buffer));
+        buffer.push(3);
+        assert!(watcher.has_changed(&buffer));
+    }
+
+    #[test]
+    fn test_has_changed_differs_from_is_valid() {
+        let mut buffer = GenerationalBuffer::new(2);
+        let h1 = buffer.push(1);
+        buffer.push(2);
+        let mut watcher = Watcher::new(&buffer, h1);
+        // a removal invalidates the handle but doesn't change the slot
+        buffer.invalidate(h1);
+        assert!(!buffer.is_valid(h1));
+        assert!(!watcher.has_changed(&buffer));
+        // the overwrite of the vacant slot is detected
+        let h3 = buffer.push(3);
+        assert_eq!(h3.index(), h1.index());
+        assert!(watcher.has_changed(&buffer));
+        watcher.mark_seen(&buffer);
+        assert!(!watcher.has_changed(&buffer));
+        buffer.clear();
+        assert!(watcher.has_changed(&buffer));
+    }
+}